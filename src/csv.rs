@@ -0,0 +1,158 @@
+use crate::{BasicRenderer, BasicRendererError, Renderer};
+use beancount::core::*;
+use std::io::prelude::*;
+
+/// Renders postings as CSV, one row per posting, for use in spreadsheets.
+#[derive(Clone, Default, Debug)]
+pub struct CsvRenderer {
+    /// Formats the dates, flags, accounts and numbers that end up in the fields.
+    pub basic: BasicRenderer,
+}
+
+impl CsvRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+const HEADER: [&str; 9] = [
+    "date",
+    "flag",
+    "payee",
+    "narration",
+    "account",
+    "number",
+    "currency",
+    "cost",
+    "price",
+];
+
+/// Whether a column is always quoted. Currencies are, so spreadsheets never read them as numbers
+/// or formulas.
+const ALWAYS_QUOTED: [bool; 9] = [false, false, false, false, false, false, true, false, false];
+
+/// Write a row, quoting fields that contain a separator, a quote or a line break, and fields of
+/// columns that are always quoted.
+fn write_row<W: Write>(
+    w: &mut W,
+    fields: &[&str],
+    always_quoted: &[bool],
+) -> Result<(), BasicRendererError> {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            write!(w, ",")?;
+        }
+        if always_quoted[i] || field.contains([',', '"', '\n', '\r']) {
+            write!(w, "\"{}\"", field.replace('"', "\"\""))?;
+        } else {
+            write!(w, "{}", field)?;
+        }
+    }
+    write!(w, "\r\n")?;
+    Ok(())
+}
+
+impl<'a, W: Write> Renderer<&'a Ledger<'_>, W> for CsvRenderer {
+    type Error = BasicRendererError;
+    fn render(&self, ledger: &'a Ledger<'_>, w: &mut W) -> Result<(), Self::Error> {
+        write_row(w, &HEADER, &[false; 9])?;
+        for directive in &ledger.directives {
+            if let Directive::Transaction(transaction) = directive {
+                self.render(transaction, w)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> Renderer<&'a Transaction<'_>, W> for CsvRenderer {
+    type Error = BasicRendererError;
+    fn render(&self, transaction: &'a Transaction<'_>, w: &mut W) -> Result<(), Self::Error> {
+        let date = self.basic.format_date(&transaction.date);
+        let flag = self.basic.render_to_string(&transaction.flag)?;
+        let payee = transaction.payee.unwrap_or_default();
+        for posting in &transaction.postings {
+            let currency = posting
+                .units
+                .currency
+                .map(|currency| self.basic.currency(currency))
+                .unwrap_or_default();
+            let account = self.basic.render_to_string(&posting.account)?;
+            let number = match &posting.units.num {
+                Some(num) => {
+                    let mut number = Vec::new();
+                    self.basic
+                        .write_number(&mut number, num, posting.units.currency)?;
+                    String::from_utf8(number)?
+                }
+                None => String::new(),
+            };
+            let cost = match &posting.cost {
+                Some(cost) => self.basic.render_to_string(cost)?,
+                None => String::new(),
+            };
+            let price = match &posting.price {
+                Some(price) => self.basic.render_to_string(price)?,
+                None => String::new(),
+            };
+            write_row(
+                w,
+                &[
+                    &date,
+                    &flag,
+                    payee,
+                    transaction.narration,
+                    &account,
+                    &number,
+                    &currency,
+                    &cost,
+                    &price,
+                ],
+                &ALWAYS_QUOTED,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(source: &str) -> String {
+        let ledger = beancount::parse(source).expect("valid ledger");
+        let mut rendered = Vec::new();
+        CsvRenderer::new()
+            .render(&ledger, &mut rendered)
+            .expect("renders");
+        String::from_utf8(rendered).expect("utf-8")
+    }
+
+    #[test]
+    fn one_row_per_posting() {
+        let rendered = render(
+            "2019-01-02 * \"Shop, Inc.\" \"Shares\"\n\
+             \tAssets:Broker\t10 AAPL {100 USD} @ 110 USD\n\
+             \tAssets:Cash\t-1000 USD\n",
+        );
+        assert_eq!(
+            rendered,
+            "date,flag,payee,narration,account,number,currency,cost,price\r\n\
+             2019-01-02,*,\"Shop, Inc.\",Shares,Assets:Broker,10,\"AAPL\",{100 USD},110 USD\r\n\
+             2019-01-02,*,\"Shop, Inc.\",Shares,Assets:Cash,-1000,\"USD\",,\r\n"
+        );
+    }
+
+    #[test]
+    fn currencies_are_quoted() {
+        let rendered = render("2019-01-02 * \"Coffee\"\n  Assets:Cash -3 USD\n  Expenses:Food\n");
+        let rows: Vec<_> = rendered.lines().skip(1).collect();
+        assert_eq!(
+            rows,
+            [
+                "2019-01-02,*,,Coffee,Assets:Cash,-3,\"USD\",,",
+                "2019-01-02,*,,Coffee,Expenses:Food,,\"\",,",
+            ]
+        );
+    }
+}
@@ -0,0 +1,315 @@
+use crate::{BasicRenderer, BasicRendererError, Renderer};
+use beancount::core::*;
+use rust_decimal::Decimal;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::io::prelude::*;
+
+/// Renders directives as JSON Lines: one JSON object per directive, with a `type` field naming the
+/// kind of directive. Numbers are kept as strings so no precision is lost.
+#[derive(Clone, Default, Debug)]
+pub struct JsonlRenderer {
+    /// Formats the dates, accounts and amounts that end up in the objects.
+    pub basic: BasicRenderer,
+}
+
+fn meta(meta: &HashMap<&str, &str>) -> Value {
+    let mut object = Map::new();
+    for (key, value) in meta {
+        object.insert(key.to_string(), Value::from(*value));
+    }
+    Value::Object(object)
+}
+
+fn strings(strings: &[&str]) -> Value {
+    Value::Array(strings.iter().map(|s| Value::from(*s)).collect())
+}
+
+impl JsonlRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A number formatted like the numbers of amounts in `currency`.
+    fn number(&self, num: &Decimal, currency: &str) -> Result<String, BasicRendererError> {
+        let mut number = Vec::new();
+        self.basic.write_number(&mut number, num, Some(currency))?;
+        Ok(String::from_utf8(number)?)
+    }
+
+    fn posting(&self, posting: &Posting<'_>) -> Result<Value, BasicRendererError> {
+        let mut object = Map::new();
+        object.insert(
+            "account".to_string(),
+            self.basic.render_to_string(&posting.account)?.into(),
+        );
+        object.insert(
+            "units".to_string(),
+            self.basic.render_to_string(&posting.units)?.into(),
+        );
+        let cost = match &posting.cost {
+            Some(cost) => self.basic.render_to_string(cost)?.into(),
+            None => Value::Null,
+        };
+        object.insert("cost".to_string(), cost);
+        let price = match &posting.price {
+            Some(price) => self.basic.render_to_string(price)?.into(),
+            None => Value::Null,
+        };
+        object.insert("price".to_string(), price);
+        let flag = match &posting.flag {
+            Some(flag) => self.basic.render_to_string(flag)?.into(),
+            None => Value::Null,
+        };
+        object.insert("flag".to_string(), flag);
+        object.insert("meta".to_string(), meta(&posting.meta));
+        Ok(Value::Object(object))
+    }
+
+    fn directive(&self, directive: &Directive<'_>) -> Result<Value, BasicRendererError> {
+        let basic = &self.basic;
+        let mut object = Map::new();
+        let mut insert = |key: &str, value: Value| {
+            object.insert(key.to_string(), value);
+        };
+        match directive {
+            Directive::Open(open) => {
+                insert("type", "open".into());
+                insert("date", basic.format_date(&open.date).into());
+                insert("account", basic.render_to_string(&open.account)?.into());
+                let currencies = open
+                    .currencies
+                    .iter()
+                    .map(|currency| Value::from(basic.currency(currency)))
+                    .collect();
+                insert("currencies", Value::Array(currencies));
+                let booking = match open.booking {
+                    Booking::Strict => "strict".into(),
+                    Booking::None => Value::Null,
+                    Booking::Average => "average".into(),
+                    Booking::Fifo => "fifo".into(),
+                    Booking::Lifo => "lifo".into(),
+                };
+                insert("booking", booking);
+                insert("meta", meta(&open.meta));
+            }
+            Directive::Close(close) => {
+                insert("type", "close".into());
+                insert("date", basic.format_date(&close.date).into());
+                insert("account", basic.render_to_string(&close.account)?.into());
+                insert("meta", meta(&close.meta));
+            }
+            Directive::Balance(balance) => {
+                insert("type", "balance".into());
+                insert("date", basic.format_date(&balance.date).into());
+                insert("account", basic.render_to_string(&balance.account)?.into());
+                insert("amount", basic.render_to_string(&balance.amount)?.into());
+                let tolerance = match &balance.tolerance {
+                    Some(tolerance) => self.number(tolerance, balance.amount.currency)?.into(),
+                    None => Value::Null,
+                };
+                insert("tolerance", tolerance);
+                insert("meta", meta(&balance.meta));
+            }
+            Directive::Option(option) => {
+                insert("type", "option".into());
+                insert("name", option.name.into());
+                insert("value", option.val.into());
+            }
+            Directive::Commodity(commodity) => {
+                insert("type", "commodity".into());
+                insert("date", basic.format_date(&commodity.date).into());
+                insert("currency", basic.currency(commodity.name).into());
+                insert("meta", meta(&commodity.meta));
+            }
+            Directive::Custom(custom) => {
+                insert("type", "custom".into());
+                insert("date", basic.format_date(&custom.date).into());
+                insert("name", custom.name.into());
+                insert("args", strings(&custom.args));
+                insert("meta", meta(&custom.meta));
+            }
+            Directive::Document(document) => {
+                insert("type", "document".into());
+                insert("date", basic.format_date(&document.date).into());
+                insert("account", basic.render_to_string(&document.account)?.into());
+                insert("path", document.path.into());
+                insert("tags", strings(&document.tags));
+                insert("links", strings(&document.links));
+                insert("meta", meta(&document.meta));
+            }
+            Directive::Event(event) => {
+                insert("type", "event".into());
+                insert("date", basic.format_date(&event.date).into());
+                insert("name", event.name.into());
+                insert("description", event.description.into());
+                insert("meta", meta(&event.meta));
+            }
+            Directive::Include(include) => {
+                insert("type", "include".into());
+                insert("filename", include.filename.into());
+            }
+            Directive::Note(note) => {
+                insert("type", "note".into());
+                insert("date", basic.format_date(&note.date).into());
+                insert("account", basic.render_to_string(&note.account)?.into());
+                insert("comment", note.comment.into());
+                insert("tags", strings(&note.tags));
+                insert("links", strings(&note.links));
+                insert("meta", meta(&note.meta));
+            }
+            Directive::Pad(pad) => {
+                insert("type", "pad".into());
+                insert("date", basic.format_date(&pad.date).into());
+                insert("account", basic.render_to_string(&pad.pad_to_account)?.into());
+                insert(
+                    "source_account",
+                    basic.render_to_string(&pad.pad_from_account)?.into(),
+                );
+                insert("meta", meta(&pad.meta));
+            }
+            Directive::Plugin(plugin) => {
+                insert("type", "plugin".into());
+                insert("module", plugin.module.into());
+                insert("config", plugin.config.map_or(Value::Null, Value::from));
+            }
+            Directive::Price(price) => {
+                insert("type", "price".into());
+                insert("date", basic.format_date(&price.date).into());
+                insert("currency", basic.currency(price.currency).into());
+                insert("amount", basic.render_to_string(&price.amount)?.into());
+                insert("meta", meta(&price.meta));
+            }
+            Directive::Query(query) => {
+                insert("type", "query".into());
+                insert("date", basic.format_date(&query.date).into());
+                insert("name", query.name.into());
+                insert("query_string", query.query_string.into());
+                insert("meta", meta(&query.meta));
+            }
+            Directive::Transaction(transaction) => {
+                insert("type", "transaction".into());
+                insert("date", basic.format_date(&transaction.date).into());
+                insert("flag", basic.render_to_string(&transaction.flag)?.into());
+                insert("payee", transaction.payee.map_or(Value::Null, Value::from));
+                insert("narration", transaction.narration.into());
+                insert("tags", strings(&transaction.tags));
+                insert("links", strings(&transaction.links));
+                let postings = transaction
+                    .postings
+                    .iter()
+                    .map(|posting| self.posting(posting))
+                    .collect::<Result<Vec<_>, _>>()?;
+                insert("postings", Value::Array(postings));
+                insert("meta", meta(&transaction.meta));
+            }
+            Directive::Unsupported => {
+                return Err(BasicRendererError::Unsupported { index: None })
+            }
+        }
+        Ok(Value::Object(object))
+    }
+}
+
+impl<'a, W: Write> Renderer<&'a Ledger<'_>, W> for JsonlRenderer {
+    type Error = BasicRendererError;
+    fn render(&self, ledger: &'a Ledger<'_>, w: &mut W) -> Result<(), Self::Error> {
+        for (i, directive) in ledger.directives.iter().enumerate() {
+            if let Directive::Unsupported = directive {
+                if !self.basic.skip_unsupported {
+                    return Err(BasicRendererError::Unsupported { index: Some(i) });
+                }
+            }
+            self.render(directive, w)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> Renderer<&'a Directive<'_>, W> for JsonlRenderer {
+    type Error = BasicRendererError;
+    fn render(&self, directive: &'a Directive<'_>, w: &mut W) -> Result<(), Self::Error> {
+        if self.basic.skip_unsupported && matches!(directive, Directive::Unsupported) {
+            return Ok(());
+        }
+        writeln!(w, "{}", self.directive(directive)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(renderer: &JsonlRenderer, ledger: &Ledger<'_>) -> Vec<Value> {
+        let mut rendered = Vec::new();
+        renderer.render(ledger, &mut rendered).expect("renders");
+        String::from_utf8(rendered)
+            .expect("utf-8")
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("valid json"))
+            .collect()
+    }
+
+    #[test]
+    fn transaction_with_postings() {
+        let ledger = beancount::parse(
+            "2019-01-02 * \"Cafe\" \"Coffee\" #food\n  Assets:Cash -3 USD\n  Expenses:Food\n",
+        )
+        .expect("valid ledger");
+        let rendered = render(&JsonlRenderer::new(), &ledger);
+        assert_eq!(rendered.len(), 1);
+        let transaction = &rendered[0];
+        assert_eq!(transaction["type"], "transaction");
+        assert_eq!(transaction["payee"], "Cafe");
+        assert_eq!(transaction["tags"], serde_json::json!(["#food"]));
+        let postings = transaction["postings"].as_array().expect("postings array");
+        assert_eq!(postings.len(), 2);
+        assert_eq!(postings[0]["account"], "Assets:Cash");
+        assert_eq!(postings[0]["units"], "-3 USD");
+        assert_eq!(postings[1]["account"], "Expenses:Food");
+        assert_eq!(postings[1]["units"], "");
+    }
+
+    #[test]
+    fn every_field_is_kept() {
+        let ledger = beancount::parse(
+            "2019-01-01 open Assets:Cash USD\n\
+             2019-01-01 commodity USD\n\
+             2019-01-02 balance Assets:Cash 10 ~ 0.01 USD\n\
+             2019-01-02 note Assets:Cash \"Counted\" #cash ^count\n\
+             2019-01-02 document Assets:Cash \"receipt.pdf\" #cash ^count\n\
+             2019-01-03 price USD 0.9 EUR\n",
+        )
+        .expect("valid ledger");
+        let renderer = JsonlRenderer {
+            basic: BasicRenderer {
+                currency_rename: HashMap::from([("USD".to_string(), "DLR".to_string())]),
+                ..BasicRenderer::default()
+            },
+        };
+        let rendered = render(&renderer, &ledger);
+        assert_eq!(rendered[0]["currencies"], serde_json::json!(["DLR"]));
+        assert_eq!(rendered[1]["currency"], "DLR");
+        assert_eq!(rendered[2]["amount"], "10 DLR");
+        assert_eq!(rendered[2]["tolerance"], "0.01");
+        for directive in &rendered[3..5] {
+            assert_eq!(directive["tags"], serde_json::json!(["#cash"]));
+            assert_eq!(directive["links"], serde_json::json!(["^count"]));
+        }
+        assert_eq!(rendered[5]["currency"], "DLR");
+    }
+
+    #[test]
+    fn skip_unsupported() {
+        let mut ledger = beancount::parse("2019-01-01 open Assets:Cash\n").expect("valid ledger");
+        ledger.directives.push(Directive::Unsupported);
+        let mut renderer = JsonlRenderer::new();
+        let error = renderer.render(&ledger, &mut Vec::new()).expect_err("unsupported");
+        assert!(error.to_string().ends_with("at index 1"));
+        renderer.basic.skip_unsupported = true;
+        let rendered = render(&renderer, &ledger);
+        assert_eq!(rendered.len(), 1);
+        assert_eq!(rendered[0]["type"], "open");
+    }
+}
@@ -1,372 +1,4301 @@
 use beancount::core::*;
-use std::collections::HashMap;
+use rust_decimal::{Decimal, RoundingStrategy};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{self, Display};
+use std::fs;
 use std::io;
 use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use std::str;
+use std::sync::Arc;
+use std::string::FromUtf8Error;
 use thiserror::Error;
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Default, Debug)]
-pub struct BasicRenderer {}
+#[cfg(feature = "csv")]
+mod csv;
+#[cfg(feature = "serde")]
+mod jsonl;
+mod markdown;
 
-impl BasicRenderer {
-    pub fn new() -> Self {
-        Self::default()
+#[cfg(feature = "csv")]
+pub use crate::csv::CsvRenderer;
+#[cfg(feature = "serde")]
+pub use jsonl::JsonlRenderer;
+pub use markdown::MarkdownRenderer;
+
+/// A function that may replace an account before it is rendered.
+pub type AccountRewriter = for<'a, 'b> fn(&'b Account<'a>) -> Cow<'b, Account<'a>>;
+
+/// A closure returning the comments to render directly above a directive of a ledger. Unlike a
+/// function pointer it can capture state, such as comments collected while parsing.
+#[derive(Clone)]
+pub struct CommentHook(Arc<CommentFn>);
+
+type CommentFn = dyn Fn(&Directive<'_>) -> Vec<String> + Send + Sync;
+
+impl CommentHook {
+    pub fn new(hook: impl Fn(&Directive<'_>) -> Vec<String> + Send + Sync + 'static) -> Self {
+        CommentHook(Arc::new(hook))
     }
 }
 
-pub fn render<W: Write>(w: &mut W, document: &Document<'_>) -> Result<(), BasicRendererError>{
-    BasicRenderer::default().render(document, w)
+/// Hooks are equal when they share the same closure.
+impl PartialEq for CommentHook {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
 }
 
-#[derive(Error, Debug)]
-pub enum BasicRendererError {
-    #[error("an io error occurred")]
-    Io(#[from] io::Error),
-    #[error("could not render unsupported directive")]
-    Unsupported,
+impl Eq for CommentHook {}
+
+impl fmt::Debug for CommentHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CommentHook(..)")
+    }
 }
 
-pub trait Renderer<T, W: Write> {
-    type Error;
-    fn render(&self, renderable: T, write: &mut W) -> Result<(), Self::Error>;
+/// Context from the enclosing transaction that affects how a posting is laid out.
+#[derive(Copy, Clone, Default, Debug)]
+struct PostingLayout {
+    /// Leave out the currency of the units, keeping only the number.
+    elide_currency: bool,
+    /// The column the currency of the units starts at, counted from the end of the indentation.
+    currency_column: Option<usize>,
+    /// The column the decimal point of the units is at, counted from the end of the indentation.
+    point_column: Option<usize>,
 }
 
-impl<'a, W: Write> Renderer<&'a Ledger<'_>, W> for BasicRenderer {
-    type Error = BasicRendererError;
-    fn render(&self, ledger: &'a Ledger<'_>, write: &mut W) -> Result<(), Self::Error> {
-        for directive in &ledger.directives {
-            self.render(directive, write)?;
-            writeln!(write, "")?;
-        }
-        Ok(())
-    }
+/// Adapts a `fmt::Write` target so it can be rendered into. Prefer `BasicRenderer::render_fmt`,
+/// which reports errors of the target as `fmt::Error` rather than as `io::Error`.
+pub struct FmtWriter<'a, W: fmt::Write> {
+    inner: &'a mut W,
+    error: Option<fmt::Error>,
 }
 
-impl<'a, W: Write> Renderer<&'a Document<'_>, W> for BasicRenderer {
-    type Error = BasicRendererError;
-    fn render(&self, document: &'a Document<'_>, write: &mut W) -> Result<(), Self::Error> {
-        // TODO: Tags? Links?
-        write!(write, "{} document ", document.date)?;
-        self.render(&document.account, write)?;
-        writeln!(write, " \"{}\"", document.path)?;
-        render_key_value(write, &document.meta)?;
-        Ok(())
+impl<'a, W: fmt::Write> FmtWriter<'a, W> {
+    pub fn new(inner: &'a mut W) -> Self {
+        Self { inner, error: None }
     }
 }
 
-impl<'a, W: Write> Renderer<&'a Directive<'_>, W> for BasicRenderer {
-    type Error = BasicRendererError;
-    fn render(&self, directive: &'a Directive<'_>, write: &mut W) -> Result<(), Self::Error> {
-        use Directive::*;
-        match directive {
-            Open(open) => self.render(open, write),
-            Close(close) => self.render(close, write),
-            Balance(balance) => self.render(balance, write),
-            Option(bc_option) => self.render(bc_option, write),
-            Commodity(commodity) => self.render(commodity, write),
-            Custom(custom) => self.render(custom, write),
-            Document(document) => self.render(document, write),
-            Event(event) => self.render(event, write),
-            Include(include) => self.render(include, write),
-            Note(note) => self.render(note, write),
-            Pad(pad) => self.render(pad, write),
-            Plugin(plugin) => self.render(plugin, write),
-            Price(price) => self.render(price, write),
-            Query(query) => self.render(query, write),
-            Transaction(transaction) => self.render(transaction, write),
-            Unsupported => return Err(BasicRendererError::Unsupported),
+impl<W: fmt::Write> io::Write for FmtWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let s = str::from_utf8(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if let Err(error) = self.inner.write_str(s) {
+            self.error = Some(error);
+            return Err(io::Error::other(error));
         }
+        Ok(buf.len())
     }
-}
 
-fn render_key_value<W: Write>(
-    w: &mut W,
-    kv: &HashMap<&str, &str>,
-) -> Result<(), BasicRendererError> {
-    for (key, value) in kv {
-        writeln!(w, "\t{}: {}", key, value)?;
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
     }
-    Ok(())
 }
 
-impl<'a, W: Write> Renderer<&'a Open<'_>, W> for BasicRenderer {
-    type Error = BasicRendererError;
-    fn render(&self, open: &'a Open<'_>, write: &mut W) -> Result<(), Self::Error> {
-        write!(write, "{} open ", open.date)?;
-        self.render(&open.account, write)?;
-        for (i, currency) in open.currencies.iter().enumerate() {
-            write!(write, "{}", currency)?;
-            if i < open.currencies.len() - 1 {
-                write!(write, " ")?;
+/// Statistics about a rendered ledger.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Default, Debug)]
+pub struct RenderStats {
+    pub directives: usize,
+    pub transactions: usize,
+    pub postings: usize,
+    /// The size of the rendered output in bytes.
+    pub bytes: usize,
+    /// The number of lines in the rendered output.
+    pub lines: usize,
+}
+
+impl RenderStats {
+    /// Count the directives that are rendered, leaving out unsupported ones, which are either
+    /// skipped or an error.
+    fn count(directives: &[Directive<'_>]) -> Self {
+        let mut stats = Self::default();
+        for directive in directives {
+            match directive {
+                Directive::Unsupported => continue,
+                Directive::Transaction(transaction) => {
+                    stats.transactions += 1;
+                    stats.postings += transaction.postings.len();
+                }
+                _ => {}
             }
+            stats.directives += 1;
         }
-        match open.booking {
-            Booking::Strict => write!(write, r#" "strict""#)?,
-            Booking::None => {}
-            Booking::Average => write!(write, r#" "average""#)?,
-            Booking::Fifo => write!(write, r#" "fifo""#)?,
-            Booking::Lifo => write!(write, r#" "lifo""#)?,
-        };
-        writeln!(write, "")?;
-        render_key_value(write, &open.meta)?;
-        Ok(())
+        stats
     }
-}
 
-impl<'a, W: Write> Renderer<&'a Close<'_>, W> for BasicRenderer {
-    type Error = BasicRendererError;
-    fn render(&self, close: &'a Close<'_>, write: &mut W) -> Result<(), Self::Error> {
-        write!(write, "{} close ", close.date)?;
-        self.render(&close.account, write)?;
-        writeln!(write, "")?;
-        render_key_value(write, &close.meta)?;
-        Ok(())
+    /// A rough estimate of the rendered size in bytes, used to preallocate output buffers.
+    fn estimated_bytes(&self) -> usize {
+        self.directives * 64 + self.postings * 48
     }
 }
 
-impl<'a, W: Write> Renderer<&'a Account<'_>, W> for BasicRenderer {
-    type Error = BasicRendererError;
-    fn render(&self, account: &'a Account<'_>, write: &mut W) -> Result<(), Self::Error> {
-        write!(
-            write,
-            "{}:{}",
-            match account.ty {
-                AccountType::Assets => "Assets",
-                AccountType::Liabilities => "Liabilities",
-                AccountType::Equity => "Equity",
-                AccountType::Income => "Income",
-                AccountType::Expenses => "Expenses",
-            },
-            account.parts.join(":")
-        )?;
-        Ok(())
-    }
+/// How far `render_entries` got, so a later call can continue where an earlier one stopped.
+#[derive(Copy, Clone, Default)]
+struct RenderState {
+    /// The date of the last directive written, `None` before the first one.
+    written: Option<Option<(i32, u32, u32)>>,
+    /// The last date of a dated directive written.
+    last_date: Option<(i32, u32, u32)>,
 }
 
-impl<'a, W: Write> Renderer<&'a Balance<'_>, W> for BasicRenderer {
-    type Error = BasicRendererError;
-    fn render(&self, balance: &'a Balance<'_>, w: &mut W) -> Result<(), Self::Error> {
-        write!(w, "{} balance ", balance.date)?;
-        self.render(&balance.account, w)?;
-        write!(w, "\t")?;
-        self.render(&balance.amount, w)?;
-        writeln!(w, "")?;
-        render_key_value(w, &balance.meta)?;
-        Ok(())
-    }
+/// What `render_entries` needs to know about a whole ledger up front.
+struct LedgerFacts<'a, 'b> {
+    directives: &'a [Directive<'b>],
+    /// The currencies posted to each account, for `warn_unused_currencies`.
+    used_currencies: HashMap<String, HashSet<&'b str>>,
+    /// The accounts and currencies last posted to by each directive, for `trailing_balances`.
+    trailing_balances: HashMap<usize, Vec<(&'a Account<'b>, Vec<&'b str>)>>,
 }
 
-impl<'a, W: Write> Renderer<&'a Amount<'_>, W> for BasicRenderer {
-    type Error = BasicRendererError;
-    fn render(&self, amount: &'a Amount<'_>, w: &mut W) -> Result<(), Self::Error> {
-        write!(w, "{} {}", amount.num, amount.currency)?;
-        Ok(())
-    }
+/// How literal tab characters inside rendered strings are written.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Default, Debug)]
+pub enum TabEscape {
+    /// Escape tabs as `\t`.
+    #[default]
+    Escape,
+    /// Replace every tab with a single space. This is lossy, but avoids tabs in the output.
+    Spaces,
 }
 
-impl<'a, W: Write> Renderer<&'a BcOption<'_>, W> for BasicRenderer {
-    type Error = BasicRendererError;
-    fn render(&self, option: &'a BcOption<'_>, w: &mut W) -> Result<(), Self::Error> {
-        writeln!(w, "option \"{}\" \"{}\"", option.name, option.val)?;
-        Ok(())
-    }
+/// The indentation of postings and metadata.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Default, Debug)]
+pub enum Indent {
+    /// One tab per level.
+    #[default]
+    Tab,
+    /// The given number of spaces per level.
+    Spaces(usize),
 }
 
-impl<'a, W: Write> Renderer<&'a Commodity<'_>, W> for BasicRenderer {
-    type Error = BasicRendererError;
-    fn render(&self, commodity: &'a Commodity<'_>, w: &mut W) -> Result<(), Self::Error> {
-        writeln!(w, "{} commodity {}", commodity.date, commodity.name)?;
-        render_key_value(w, &commodity.meta)
-    }
+/// The line ending written after every line.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Default, Debug)]
+pub enum LineEnding {
+    /// `\n`
+    #[default]
+    Lf,
+    /// `\r\n`
+    Crlf,
 }
 
-impl<'a, W: Write> Renderer<&'a Custom<'_>, W> for BasicRenderer {
-    type Error = BasicRendererError;
-    fn render(&self, custom: &'a Custom<'_>, w: &mut W) -> Result<(), Self::Error> {
-        writeln!(
-            w,
-            "{} custom \"{}\" {}",
-            custom.date,
-            custom.name,
-            custom.args.join(" ")
-        )?;
-        render_key_value(w, &custom.meta)
-    }
+/// How the flag of a completed transaction is written. Beancount accepts both.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Default, Debug)]
+pub enum FlagStyle {
+    /// `2019-01-31 * "Narration"`
+    #[default]
+    Symbol,
+    /// `2019-01-31 txn "Narration"`
+    Txn,
 }
 
-impl<'a, W: Write> Renderer<&'a Event<'_>, W> for BasicRenderer {
-    type Error = BasicRendererError;
-    fn render(&self, event: &'a Event<'_>, w: &mut W) -> Result<(), Self::Error> {
-        writeln!(w, "{} event \"{}\" \"{}\"", event.date, event.name, event.description)?;
-        render_key_value(w, &event.meta)
-    }
+/// How the amounts of postings are aligned when `align_currency` is set.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Default, Debug)]
+pub enum Alignment {
+    /// Line up the currencies.
+    #[default]
+    Currency,
+    /// Line up the decimal points of the numbers, and the currencies after them.
+    DecimalPoint,
+    /// Line up the currencies of all postings and balances of a ledger at the same column, found
+    /// by measuring every directive before rendering. Elsewhere this is the same as `Currency`.
+    WholeFile,
 }
 
-impl<'a, W: Write> Renderer<&'a Include<'_>, W> for BasicRenderer {
-    type Error = BasicRendererError;
-    fn render(&self, include: &'a Include<'_>, w: &mut W) -> Result<(), Self::Error> {
-        writeln!(w, "include {}", include.filename)?;
-        Ok(())
-    }
+/// Where the directives of a ledger are separated by a blank line.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Default, Debug)]
+pub enum Separator {
+    /// Between every two directives.
+    #[default]
+    Single,
+    /// Nowhere.
+    None,
+    /// Between directives with different dates only.
+    GroupByDate,
 }
 
-impl<'a, W: Write> Renderer<&'a Note<'_>, W> for BasicRenderer {
-    type Error = BasicRendererError;
-    fn render(&self, note: &'a Note<'_>, w: &mut W) -> Result<(), Self::Error> {
-        write!(w, "{} note ", note.date)?;
-        self.render(&note.account, w)?;
-        writeln!(w, " \"{}\"", note.comment)?;
-        render_key_value(w, &note.meta)
-    }
+/// The format dates are rendered in. Beancount accepts both.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Default, Debug)]
+pub enum DateFormat {
+    /// `2019-01-31`
+    #[default]
+    Dashes,
+    /// `2019/01/31`
+    Slashes,
 }
 
-impl<'a, W: Write> Renderer<&'a Pad<'_>, W> for BasicRenderer {
-    type Error = BasicRendererError;
-    fn render(&self, pad: &'a Pad<'_>, w: &mut W) -> Result<(), Self::Error> {
-        write!(w, "{} pad ", pad.date)?;
-        self.render(&pad.pad_to_account, w)?;
-        write!(w, " ")?;
-        self.render(&pad.pad_from_account, w)?;
-        writeln!(w, "")?;
-        render_key_value(w, &pad.meta)
-    }
+/// The order in which the postings of a transaction are rendered.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Default, Debug)]
+pub enum PostingSort {
+    /// Keep the postings in the order they were parsed in.
+    #[default]
+    Preserve,
+    /// Move postings without a number, whose amount is interpolated, to the end. The other
+    /// postings keep their order.
+    ElidedLast,
 }
 
-impl<'a, W: Write> Renderer<&'a Plugin<'_>, W> for BasicRenderer {
-    type Error = BasicRendererError;
-    fn render(&self, plugin: &'a Plugin<'_>, w: &mut W) -> Result<(), Self::Error> {
-        write!(w, "plugin \"{}\"", plugin.module)?;
-        if let Some(config) = &plugin.config {
-            write!(w, " \"{}\"", config)?;
+/// The order in which metadata entries are rendered.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Default, Debug)]
+pub enum MetaOrder {
+    /// Sorted by key.
+    #[default]
+    Sorted,
+    /// In the order they were parsed in. This currently falls back to `Sorted`, because the
+    /// beancount core stores metadata in a `HashMap`.
+    AsParsed,
+}
+
+/// A calendar period.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Period {
+    Month,
+    Year,
+}
+
+impl Period {
+    /// Identifies the period a date falls in.
+    fn of(self, (year, month, _): (i32, u32, u32)) -> (i32, u32) {
+        match self {
+            Period::Month => (year, month),
+            Period::Year => (year, 0),
         }
-        writeln!(w, "")?;
-        Ok(())
     }
 }
 
-impl<'a, W: Write> Renderer<&'a Price<'_>, W> for BasicRenderer {
-    type Error = BasicRendererError;
-    fn render(&self, price: &'a Price<'_>, w: &mut W) -> Result<(), Self::Error> {
-        write!(w, "{} price {} ", price.date, price.currency)?;
-        self.render(&price.amount, w)?;
-        writeln!(w, "")?;
-        render_key_value(w, &price.meta)
-    }
+/// How metadata entries with an empty value are written.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Default, Debug)]
+pub enum EmptyMetaValue {
+    /// `key:`
+    #[default]
+    BareColon,
+    /// `key: ""`
+    EmptyQuoted,
 }
 
-impl<'a, W: Write> Renderer<&'a Query<'_>, W> for BasicRenderer {
-    type Error = BasicRendererError;
-    fn render(&self, query: &'a Query<'_>, w: &mut W) -> Result<(), Self::Error> {
-        writeln!(w, "{} query \"{}\" \"{}\"", query.date, query.name, query.query_string)?;
-        render_key_value(w, &query.meta)
-    }
+/// Which flags are accepted in strict mode.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Default, Debug)]
+pub enum FlagPolicy {
+    /// Accept every flag beancount can read back.
+    #[default]
+    AllowOther,
+    /// Only accept `*` and `!`, rejecting `Flag::Other`.
+    RejectOther,
 }
 
-impl<'a, W: Write> Renderer<&'a Transaction<'_>, W> for BasicRenderer {
-    type Error = BasicRendererError;
-    fn render(&self, transaction: &'a Transaction<'_>, w: &mut W) -> Result<(), Self::Error> {
-        write!(w, "{} ", transaction.date)?;
-        self.render(&transaction.flag, w)?;
-        if let Some(payee) = &transaction.payee {
-            write!(w, " \"{}\"", payee)?;
-        }
-        write!(w, " \"{}\"", &transaction.narration)?;
-        for tag in &transaction.tags {
-            write!(w, " {}", tag)?;
-        }
-        for link in &transaction.links {
-            write!(w, " {}", link)?;
-        }
-        for posting in &transaction.postings {
-            self.render(posting, w)?;
+// `account_rewriter` is compared by address. The same function may have several addresses, so
+// two renderers may compare unequal while behaving the same, but never the other way around.
+#[allow(unpredictable_function_pointer_comparisons)]
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct BasicRenderer {
+    /// When set, the text following a directive keyword (`open`, `close`, `balance`, ...) is
+    /// padded to start at this column. The keyword is always followed by at least one space.
+    pub keyword_column: Option<usize>,
+    /// Append a `; N directives, M transactions` comment after the last directive of a ledger.
+    pub append_summary: bool,
+    /// Prefix every directive of a ledger with a `; [#N]` comment holding its index.
+    pub debug_indices: bool,
+    /// Emit a `; WARNING: date out of order` comment above directives dated before the
+    /// directive preceding them.
+    pub warn_date_order: bool,
+    /// Render the directives of a ledger sorted by date. Directives without a date, such as
+    /// `option`, come first, and directives on the same date keep their original order.
+    pub sort_by_date: bool,
+    /// Emit a comment above every `open` that allows a currency no posting to the account uses.
+    pub warn_unused_currencies: bool,
+    /// Emit a `; Payee: X` comment above every run of consecutive transactions sharing a payee.
+    pub payee_banners: bool,
+    /// Consulted for every directive of a ledger. Each returned comment is rendered as `; ...`
+    /// directly above the directive. The core does not keep the comments of a parsed file, so
+    /// this is the way to add them back.
+    pub leading_comments: Option<CommentHook>,
+    /// Add a `checksum` metadata entry to every dated directive of a ledger, computed from its
+    /// rendered content.
+    pub checksums: bool,
+    /// Insert an extra blank line before a `balance` that falls in a different period than the
+    /// directive before it.
+    pub balance_period_break: Option<Period>,
+    /// The number of decimal places to render numbers with, keyed by currency. Currencies that
+    /// are missing from the map use `default_precision`.
+    pub currency_precision: HashMap<String, u32>,
+    /// The number of decimal places to render numbers with when the currency has no entry in
+    /// `currency_precision`. When `None`, numbers are rendered as they were parsed.
+    pub default_precision: Option<u32>,
+    /// Consulted for every rendered account, e.g. to anonymize or migrate account names.
+    pub account_rewriter: Option<AccountRewriter>,
+    /// How tabs inside strings such as narrations and metadata values are written.
+    pub tab_escape: TabEscape,
+    /// Render the default booking of an `open` as `"NONE"` instead of omitting it.
+    pub explicit_booking_none: bool,
+    /// How the flag of a completed transaction is written.
+    pub flag_style: FlagStyle,
+    /// The format every date is rendered in.
+    pub date_format: DateFormat,
+    /// The indentation of postings and metadata. Metadata of a posting is indented one level
+    /// deeper than the posting itself.
+    pub indent: Indent,
+    /// The line ending written after every line.
+    pub line_ending: LineEnding,
+    /// Where the directives of a ledger are separated by a blank line.
+    pub separator: Separator,
+    /// Emit an `open` at the top of a ledger for every account that is used without being opened.
+    /// Each synthetic `open` is dated at the earliest directive referencing the account.
+    pub synthesize_opens: bool,
+    /// After the last transaction of every account, emit a placeholder `balance` of `0` for each
+    /// currency posted to it, to be filled in when reconciling. It is dated the day after the
+    /// transaction, as a balance is checked against the start of its day.
+    pub trailing_balances: bool,
+    /// When set, the account of a posting starts at this column, counted from the end of the
+    /// posting indentation. Flagged and unflagged postings then line up.
+    pub account_column: Option<usize>,
+    /// Written between the account and the units of a posting. A tab by default. When
+    /// `align_currency` is set, every tab is written as two spaces so the columns can be counted.
+    pub posting_amount_prefix: Cow<'static, str>,
+    /// Render the flags of postings. Enabled by default; when disabled, flags are stripped.
+    pub keep_posting_flags: bool,
+    /// When set, the `@` of a posting price starts at this column, counted in characters from the
+    /// end of the posting indentation.
+    pub price_column: Option<usize>,
+    /// Line up the currencies of the posting amounts of a transaction. The currencies start at
+    /// this column, counted from the end of the posting indentation, or further to the right when
+    /// a posting would not fit. Tabs in `posting_amount_prefix` are written as two spaces. The
+    /// amounts of `balance` assertions are lined up with them, which works out best when indenting
+    /// with spaces.
+    pub align_currency: Option<usize>,
+    /// How `align_currency` lines up the amounts.
+    pub alignment: Alignment,
+    /// Render posting prices as total prices with `@@` instead of per-unit prices with `@`. The
+    /// core does not record which of the two a price was written as, so this applies to every
+    /// posting and should match how the prices were authored.
+    pub total_prices: bool,
+    /// The order in which postings are rendered.
+    pub posting_sort: PostingSort,
+    /// Skip directives the core could not represent instead of failing with
+    /// `BasicRendererError::Unsupported`.
+    pub skip_unsupported: bool,
+    /// Validate values before rendering them, returning an error instead of writing output that
+    /// beancount would reject.
+    pub strict: bool,
+    /// Which transaction flags are accepted in strict mode.
+    pub transaction_flag_policy: FlagPolicy,
+    /// Which posting flags are accepted in strict mode.
+    pub posting_flag_policy: FlagPolicy,
+    /// Emit a `; WARNING: N postings` comment above transactions with more postings than this.
+    pub max_postings_warn: Option<usize>,
+    /// Emit a `; WARNING: long narration` comment above transactions whose narration is longer
+    /// than this many characters.
+    pub narration_warn_len: Option<usize>,
+    /// How metadata entries with an empty value are written.
+    pub empty_meta_value: EmptyMetaValue,
+    /// Write metadata values and `custom` arguments that look like numbers, dates, `TRUE` or
+    /// `FALSE`, accounts, currencies or amounts without quotes. The core stores every value as a
+    /// string, so this is a guess: a string such as `"0042"` is read back as a number. Disabled by
+    /// default, quoting every value.
+    pub infer_value_types: bool,
+    /// Rendered as the narration of transactions that have neither a payee nor a narration.
+    pub narration_placeholder: Option<String>,
+    /// Payees that are renamed when rendering transactions, e.g. to normalize imported payees.
+    pub payee_rename: HashMap<String, String>,
+    /// Emit a `; net: 0 USD` comment after every transaction, summing the units of its postings
+    /// per currency. A balanced transaction without costs or prices nets to zero.
+    pub net_comment: bool,
+    /// Render metadata of directives and postings. Enabled by default. When disabled, the comments
+    /// of `meta_comment_keys` and the entries of `checksums` are left out as well.
+    pub render_meta: bool,
+    /// The order in which metadata entries are rendered.
+    pub meta_order: MetaOrder,
+    /// Transaction metadata with these keys is rendered as a `; key: value` comment instead of a
+    /// metadata line, for example the `import-id` left behind by an importer.
+    pub meta_comment_keys: HashSet<String>,
+    /// Document paths inside this directory are rendered relative to it. Other paths are left
+    /// untouched.
+    pub document_base: Option<PathBuf>,
+    /// Uppercase every currency, as beancount requires. Useful for imports that produce lowercase
+    /// or mixed-case commodities.
+    pub uppercase_currencies: bool,
+    /// Currencies that are renamed wherever they are rendered.
+    pub currency_rename: HashMap<String, String>,
+    /// Group the integer part of numbers in thousands with this separator, as in `1,000,000.00`.
+    /// Beancount does not accept grouped numbers, so the output cannot be parsed back.
+    pub group_digits: Option<char>,
+    /// Write a `+` in front of positive numbers. Beancount does not accept it, so the output
+    /// cannot be parsed back.
+    pub explicit_sign: bool,
+    /// Enable the display-only options below. Output rendered with this set is meant for reading
+    /// and is not guaranteed to be accepted by beancount.
+    pub display_mode: bool,
+    /// In display mode, currencies that appear in this map are replaced by the symbol they map
+    /// to, written directly after the number (`10€` rather than `10 EUR`).
+    pub currency_symbols: HashMap<String, String>,
+    /// In display mode, the units of postings to accounts with one of these roots are rendered
+    /// with their sign flipped, e.g. to show income as positive. The ledger itself is unchanged.
+    pub sign_convention: HashSet<AccountType>,
+    /// In display mode, when all postings of a transaction share a currency, only the first
+    /// posting shows it.
+    pub elide_currencies: bool,
+    /// In display mode, accounts deeper than this many levels, counting the root, are collapsed
+    /// into their ancestor at this depth. A depth of 0 is treated as 1, leaving only the root.
+    pub max_account_depth: Option<usize>,
+}
+
+impl Default for BasicRenderer {
+    fn default() -> Self {
+        Self {
+            keyword_column: None,
+            append_summary: false,
+            debug_indices: false,
+            warn_date_order: false,
+            sort_by_date: false,
+            warn_unused_currencies: false,
+            payee_banners: false,
+            leading_comments: None,
+            checksums: false,
+            balance_period_break: None,
+            currency_precision: HashMap::new(),
+            default_precision: None,
+            account_rewriter: None,
+            tab_escape: TabEscape::default(),
+            explicit_booking_none: false,
+            flag_style: FlagStyle::default(),
+            date_format: DateFormat::default(),
+            indent: Indent::default(),
+            line_ending: LineEnding::default(),
+            separator: Separator::default(),
+            synthesize_opens: false,
+            trailing_balances: false,
+            account_column: None,
+            posting_amount_prefix: Cow::Borrowed("\t"),
+            keep_posting_flags: true,
+            price_column: None,
+            align_currency: None,
+            alignment: Alignment::default(),
+            total_prices: false,
+            posting_sort: PostingSort::default(),
+            skip_unsupported: false,
+            strict: false,
+            transaction_flag_policy: FlagPolicy::default(),
+            posting_flag_policy: FlagPolicy::default(),
+            max_postings_warn: None,
+            narration_warn_len: None,
+            empty_meta_value: EmptyMetaValue::default(),
+            infer_value_types: false,
+            narration_placeholder: None,
+            payee_rename: HashMap::new(),
+            net_comment: false,
+            render_meta: true,
+            meta_order: MetaOrder::default(),
+            meta_comment_keys: HashSet::new(),
+            document_base: None,
+            uppercase_currencies: false,
+            currency_rename: HashMap::new(),
+            display_mode: false,
+            currency_symbols: HashMap::new(),
+            sign_convention: HashSet::new(),
+            elide_currencies: false,
+            max_account_depth: None,
+            group_digits: None,
+            explicit_sign: false,
         }
-        render_key_value(w, &transaction.meta)
     }
 }
 
-impl<'a, W: Write> Renderer<&'a Posting<'_>, W> for BasicRenderer {
-    type Error = BasicRendererError;
-    fn render(&self, posting: &'a Posting<'_>, w: &mut W) -> Result<(), Self::Error> {
-        write!(w, "\t")?;
-        if let Some(flag) = &posting.flag {
-            self.render(flag, w)?;
-            write!(w, " ")?;
-        }
-        self.render(&posting.account, w)?;
-        write!(w, "\t")?;
-        self.render(&posting.units, w)?;
-        if let Some(price) = &posting.price {
-            write!(w, " @ ")?;
-            self.render(price, w)?;
+impl BasicRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Render a ledger into one buffer per account root. Each directive is placed by its primary
+    /// account: the account of an `open`, `close`, `balance`, `document` or `note`, the padded
+    /// account of a `pad`, and the account of the first posting of a transaction. Transactions
+    /// touching several roots are therefore not duplicated. Directives without an account, such as
+    /// `option` or `price`, are left out.
+    pub fn render_split_by_root(
+        &self,
+        ledger: &Ledger<'_>,
+    ) -> Result<HashMap<AccountType, String>, BasicRendererError> {
+        let mut roots: HashMap<AccountType, Vec<(usize, &Directive<'_>)>> = HashMap::new();
+        for (i, directive) in ledger.directives.iter().enumerate() {
+            if let Some(account) = primary_account(directive) {
+                roots.entry(account.ty).or_default().push((i, directive));
+            }
         }
-        if let Some(cost) = &posting.cost {
-            write!(w, " ")?;
-            self.render(cost, w)?;
+        roots
+            .into_iter()
+            .map(|(ty, directives)| {
+                let mut buffer = Vec::new();
+                self.render_entries(directives, None, &mut RenderState::default(), &mut buffer)?;
+                Ok((ty, String::from_utf8(buffer)?))
+            })
+            .collect()
+    }
+
+    /// Render a ledger into one buffer per `(year, month)`. Directives without a date, such as
+    /// `option`, `plugin` and `include`, share the `(0, 0)` bucket.
+    pub fn render_split_by_month(
+        &self,
+        ledger: &Ledger<'_>,
+    ) -> Result<HashMap<(i32, u32), String>, BasicRendererError> {
+        self.render_split_by_period(ledger, Period::Month)
+    }
+
+    fn render_split_by_period(
+        &self,
+        ledger: &Ledger<'_>,
+        period: Period,
+    ) -> Result<HashMap<(i32, u32), String>, BasicRendererError> {
+        let mut periods: HashMap<(i32, u32), Vec<(usize, &Directive<'_>)>> = HashMap::new();
+        for (i, directive) in ledger.directives.iter().enumerate() {
+            let key = directive_date(directive)
+                .and_then(date_parts)
+                .map_or((0, 0), |date| period.of(date));
+            periods.entry(key).or_default().push((i, directive));
         }
-        render_key_value(w, &posting.meta)
+        periods
+            .into_iter()
+            .map(|(key, directives)| {
+                let mut buffer = Vec::new();
+                self.render_entries(directives, None, &mut RenderState::default(), &mut buffer)?;
+                Ok((key, String::from_utf8(buffer)?))
+            })
+            .collect()
     }
-}
 
-impl<'a, W: Write> Renderer<&'a Flag, W> for BasicRenderer {
-    type Error = BasicRendererError;
-    fn render(&self, flag: &'a Flag, w: &mut W) -> Result<(), Self::Error> {
-        match flag {
-            Flag::Okay => write!(w, "*")?,
-            Flag::Warning => write!(w, "!")?,
-            Flag::Other(s) => write!(w, "{}", s)?
-        };
+    /// Render directives one at a time as they are produced, for example by a parser, separated
+    /// like the directives of a ledger. Options that need to see all directives up front, such as
+    /// `synthesize_opens`, `sort_by_date`, `payee_banners`, `warn_unused_currencies` or
+    /// `trailing_balances`, are not applied.
+    pub fn render_each<'a, W, I>(&self, directives: I, w: &mut W) -> Result<(), BasicRendererError>
+    where
+        W: Write,
+        I: IntoIterator<Item = &'a Directive<'a>>,
+    {
+        let directives = directives.into_iter().enumerate();
+        self.render_entries(directives, None, &mut RenderState::default(), w)
+    }
+
+    /// Render directives, each with its index, continuing from `state`. This is the one place
+    /// directives are separated and unsupported ones skipped. Options that need to see the whole
+    /// ledger are only applied when it is given.
+    fn render_entries<'a, 'b, W, I>(
+        &self,
+        directives: I,
+        ledger: Option<&LedgerFacts<'a, 'b>>,
+        state: &mut RenderState,
+        w: &mut W,
+    ) -> Result<(), BasicRendererError>
+    where
+        W: Write,
+        'b: 'a,
+        I: IntoIterator<Item = (usize, &'a Directive<'b>)>,
+    {
+        for (i, directive) in directives {
+            if let Directive::Unsupported = directive {
+                if self.skip_unsupported {
+                    continue;
+                }
+                return Err(BasicRendererError::Unsupported { index: Some(i) });
+            }
+            let date = directive_date(directive).and_then(date_parts);
+            self.write_separator(w, state.written, date)?;
+            state.written = Some(date);
+            if let (Some(period), Directive::Balance(_), Some(date), Some(last_date)) =
+                (self.balance_period_break, directive, date, state.last_date)
+            {
+                if period.of(date) != period.of(last_date) {
+                    self.newline(w)?;
+                }
+            }
+            if self.debug_indices {
+                write!(w, "; [#{}]", i)?;
+                self.newline(w)?;
+            }
+            if let Some(date) = date {
+                if self.warn_date_order && Some(date) < state.last_date {
+                    write!(w, "; WARNING: date out of order")?;
+                    self.newline(w)?;
+                }
+                state.last_date = Some(date);
+            }
+            if let Some(ledger) = ledger {
+                self.write_ledger_notes(ledger, i, directive, w)?;
+            }
+            self.render_entry(directive, w)?;
+            if let (Some(accounts), Some(date)) =
+                (ledger.and_then(|ledger| ledger.trailing_balances.get(&i)), date)
+            {
+                let next_day = next_day(date);
+                let date = format_next_day(date);
+                for (account, currencies) in accounts {
+                    for currency in currencies {
+                        self.write_separator(w, state.written, Some(next_day))?;
+                        state.written = Some(Some(next_day));
+                        let amount = Amount {
+                            num: Decimal::ZERO,
+                            currency,
+                        };
+                        self.write_balance(w, &date, account, &amount, None)?;
+                        self.newline(w)?;
+                    }
+                }
+            }
+        }
         Ok(())
     }
-}
 
-impl<'a, W: Write> Renderer<&'a CostSpec<'_>, W> for BasicRenderer {
-    type Error = BasicRendererError;
-    fn render(&self, cost: &'a CostSpec<'_>, w: &mut W) -> Result<(), Self::Error> {
-        let double_brackets = cost.number_total.is_some();
-        if double_brackets {
-            write!(w, "{{{{")?;
-        } else {
-            write!(w, "{{")?;
+    /// Write the payee banner and currency warnings that go above the directive at `i` of a
+    /// ledger.
+    fn write_ledger_notes<W: Write>(
+        &self,
+        ledger: &LedgerFacts<'_, '_>,
+        i: usize,
+        directive: &Directive<'_>,
+        w: &mut W,
+    ) -> Result<(), BasicRendererError> {
+        if self.payee_banners {
+            if let Some(payee) = transaction_payee(directive) {
+                let directives = ledger.directives;
+                let previous = i.checked_sub(1).and_then(|i| transaction_payee(&directives[i]));
+                let next = directives.get(i + 1).and_then(transaction_payee);
+                if previous != Some(payee) && next == Some(payee) {
+                    write!(w, "; Payee: {}", payee)?;
+                    self.newline(w)?;
+                }
+            }
         }
-        let mut first = true;
+        match directive {
+            Directive::Open(open) if self.warn_unused_currencies => {
+                let key = account_key(&open.account);
+                let used = ledger.used_currencies.get(&key);
+                for currency in &open.currencies {
+                    if !used.is_some_and(|used| used.contains(currency)) {
+                        write!(w, "; WARNING: currency {} of {} is never used", currency, key)?;
+                        self.newline(w)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
 
-        if let (Some(cost), Some(currency)) = (&cost.number_total.or(cost.number_per), &cost.currency) {
-            write!(w, "{} {}", cost, currency)?;
-            first = false;
+    /// Render a ledger after passing every directive through `transform`. Directives for which
+    /// the transform returns `None` are dropped.
+    pub fn render_transformed<'a, W, F>(
+        &self,
+        ledger: &Ledger<'a>,
+        transform: F,
+        w: &mut W,
+    ) -> Result<(), BasicRendererError>
+    where
+        W: Write,
+        F: FnMut(Directive<'a>) -> Option<Directive<'a>>,
+    {
+        let directives: Vec<_> = ledger
+            .directives
+            .iter()
+            .cloned()
+            .filter_map(transform)
+            .collect();
+        self.render_directives(&directives, w)
+    }
+
+    /// Render only the `open` and `close` directives of a ledger, sorted by their rendered account
+    /// name. Directives for the same account keep their original order.
+    pub fn render_sorted_accounts<W: Write>(
+        &self,
+        ledger: &Ledger<'_>,
+        w: &mut W,
+    ) -> Result<(), BasicRendererError> {
+        let mut directives = Vec::new();
+        for directive in &ledger.directives {
+            let account = match directive {
+                Directive::Open(open) => &open.account,
+                Directive::Close(close) => &close.account,
+                _ => continue,
+            };
+            let mut name = Vec::new();
+            self.render(account, &mut name)?;
+            directives.push((String::from_utf8(name)?, directive));
         }
+        directives.sort_by(|(a, _), (b, _)| a.cmp(b));
+        self.render_each(directives.into_iter().map(|(_, directive)| directive), w)
+    }
 
-        if let Some(date) = &cost.date {
-            if !first {
-                write!(w, ", ")?;
+    /// Render a `pad` immediately followed by the `balance` it pads towards, as a single block
+    /// without a blank line in between.
+    pub fn render_pad_balance<W: Write>(
+        &self,
+        pad: &Pad<'_>,
+        balance: &Balance<'_>,
+        w: &mut W,
+    ) -> Result<(), BasicRendererError> {
+        self.render(pad, w)?;
+        self.render(balance, w)
+    }
+
+    /// Render a ledger into a `String` preallocated from an estimate of its size, returning it
+    /// together with statistics about the ledger and the output.
+    pub fn render_to_string_sized(
+        &self,
+        ledger: &Ledger<'_>,
+    ) -> Result<(String, RenderStats), BasicRendererError> {
+        let mut stats = RenderStats::count(&ledger.directives);
+        let mut buffer = Vec::with_capacity(stats.estimated_bytes());
+        self.render(ledger, &mut buffer)?;
+        let rendered = String::from_utf8(buffer)?;
+        stats.bytes = rendered.len();
+        stats.lines = rendered.lines().count();
+        Ok((rendered, stats))
+    }
+
+    /// Render a ledger with every `include` replaced by the directives of the included file,
+    /// recursively. `path` is the file the ledger was parsed from: include paths are resolved
+    /// relative to its directory, and nested includes relative to the file containing them.
+    /// Ledger-level options such as `append_summary` are not applied.
+    pub fn render_flattened<W: Write>(
+        &self,
+        ledger: &Ledger<'_>,
+        path: &Path,
+        w: &mut W,
+    ) -> Result<(), BasicRendererError> {
+        let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+        // The root file is on the stack too, so including it again is reported as a cycle.
+        let mut including = vec![path.canonicalize()?];
+        let mut state = RenderState::default();
+        self.render_flattened_directives(
+            &ledger.directives,
+            base_dir,
+            &mut including,
+            &mut state,
+            w,
+        )
+    }
+
+    /// Render the directives of one file, continuing from the directives written before as
+    /// tracked by `state`.
+    fn render_flattened_directives<W: Write>(
+        &self,
+        directives: &[Directive<'_>],
+        base_dir: &Path,
+        including: &mut Vec<PathBuf>,
+        state: &mut RenderState,
+        w: &mut W,
+    ) -> Result<(), BasicRendererError> {
+        for (i, directive) in directives.iter().enumerate() {
+            if let Directive::Include(include) = directive {
+                let path = base_dir.join(include.filename);
+                let canonical = path.canonicalize()?;
+                if including.contains(&canonical) {
+                    return Err(BasicRendererError::IncludeCycle(path));
+                }
+                let source = fs::read_to_string(&path)?;
+                let included =
+                    beancount::parse(&source).map_err(|error| BasicRendererError::Parse {
+                        path: path.clone(),
+                        message: error.to_string(),
+                    })?;
+                including.push(canonical);
+                let dir = path.parent().unwrap_or(base_dir);
+                let included = &included.directives;
+                self.render_flattened_directives(included, dir, including, state, w)?;
+                including.pop();
+            } else {
+                self.render_entries(Some((i, directive)), None, state, w)?;
             }
-            write!(w, "{}", date)?;
-            first = false;
         }
+        Ok(())
+    }
 
-        if let Some(label) = &cost.label {
-            if !first {
-                write!(w, ", ")?;
-            }
-            write!(w, "{}", label)?;
+    /// Render a ledger and return a unified diff against a previous rendering. The diff is empty
+    /// when the output did not change.
+    #[cfg(feature = "diff")]
+    pub fn render_diff(&self, prev: &str, ledger: &Ledger<'_>) -> Result<String, BasicRendererError> {
+        let rendered = self.render_to_string(ledger)?;
+        Ok(similar::TextDiff::from_lines(prev, &rendered)
+            .unified_diff()
+            .header("previous", "rendered")
+            .to_string())
+    }
+
+    /// Render into a `fmt::Write` target such as a `String` or a `fmt::Formatter`. Errors raised
+    /// by the target are returned as `BasicRendererError::Fmt`.
+    pub fn render_fmt<T, W>(&self, renderable: T, w: &mut W) -> Result<(), BasicRendererError>
+    where
+        W: fmt::Write,
+        for<'w> Self: Renderer<T, FmtWriter<'w, W>, Error = BasicRendererError>,
+    {
+        let mut writer = FmtWriter::new(w);
+        let result = self.render(renderable, &mut writer);
+        match (result, writer.error) {
+            (Err(BasicRendererError::Io(_)), Some(error)) => Err(error.into()),
+            (result, _) => result,
         }
+    }
 
-        if double_brackets {
-            write!(w, "}}}}")?;
-        } else {
-            write!(w, "}}")?;
+    /// Render anything this renderer supports into a `String`.
+    pub fn render_to_string<T>(&self, renderable: T) -> Result<String, BasicRendererError>
+    where
+        Self: Renderer<T, Vec<u8>, Error = BasicRendererError>,
+    {
+        let mut buffer = Vec::new();
+        self.render(renderable, &mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+
+    /// Render a single directive into a `String`, without the blank line that separates the
+    /// directives of a ledger.
+    pub fn render_directive_to_string(
+        &self,
+        directive: &Directive<'_>,
+    ) -> Result<String, BasicRendererError> {
+        self.render_to_string(directive)
+    }
+
+    fn render_directives<W: Write>(
+        &self,
+        directives: &[Directive<'_>],
+        w: &mut W,
+    ) -> Result<(), BasicRendererError> {
+        if let (Alignment::WholeFile, Some(column)) = (self.alignment, self.align_currency) {
+            let renderer = BasicRenderer {
+                align_currency: Some(self.whole_file_column(directives, column)?),
+                alignment: Alignment::Currency,
+                ..self.clone()
+            };
+            return renderer.render_directives(directives, w);
+        }
+        let mut state = RenderState::default();
+        if self.synthesize_opens {
+            for (date, account) in missing_opens(directives) {
+                self.write_separator(w, state.written, date_parts(date))?;
+                self.write_keyword(w, date, "open")?;
+                self.render(account, w)?;
+                self.newline(w)?;
+                state.written = Some(date_parts(date));
+            }
+        }
+        let ledger = LedgerFacts {
+            directives,
+            used_currencies: if self.warn_unused_currencies {
+                posting_currencies(directives)
+            } else {
+                HashMap::new()
+            },
+            trailing_balances: if self.trailing_balances {
+                last_postings(directives)
+            } else {
+                HashMap::new()
+            },
+        };
+        self.render_entries(directives.iter().enumerate(), Some(&ledger), &mut state, w)?;
+        if self.append_summary {
+            self.write_separator(w, state.written, None)?;
+            let stats = RenderStats::count(directives);
+            write!(
+                w,
+                "; {} directives, {} transactions",
+                stats.directives, stats.transactions
+            )?;
+            self.newline(w)?;
         }
         Ok(())
     }
-}
 
-impl<'a, W: Write> Renderer<&'a IncompleteAmount<'_>, W> for BasicRenderer {
+    /// Render a directive of a ledger with its leading comments and checksum, if enabled.
+    fn render_entry<W: Write>(
+        &self,
+        directive: &Directive<'_>,
+        w: &mut W,
+    ) -> Result<(), BasicRendererError> {
+        if let Some(CommentHook(leading_comments)) = &self.leading_comments {
+            for comment in leading_comments(directive) {
+                for line in comment.lines() {
+                    write!(w, "; {}", line)?;
+                    self.newline(w)?;
+                }
+            }
+        }
+        if self.checksums && self.render_meta && directive_date(directive).is_some() {
+            self.render_with_checksum(directive, w)
+        } else {
+            self.render(directive, w)
+        }
+    }
+
+    /// Write the blank line `separator` asks for between a directive dated `previous` and the next
+    /// one, dated `date`. Nothing is written before the first directive, when `previous` is `None`.
+    fn write_separator<W: Write>(
+        &self,
+        w: &mut W,
+        previous: Option<Option<(i32, u32, u32)>>,
+        date: Option<(i32, u32, u32)>,
+    ) -> Result<(), BasicRendererError> {
+        let blank_line = match (previous, self.separator) {
+            (None, _) | (_, Separator::None) => false,
+            (Some(_), Separator::Single) => true,
+            (Some(previous), Separator::GroupByDate) => previous != date,
+        };
+        if blank_line {
+            self.newline(w)?;
+        }
+        Ok(())
+    }
+
+    /// Render a directive with a `checksum` metadata entry, written with the rest of its metadata.
+    /// The checksum is the 64-bit FNV-1a hash of the directive as rendered without it, so it is
+    /// deterministic for a given directive and renderer configuration. A `checksum` the directive
+    /// already has is replaced.
+    fn render_with_checksum<W: Write>(
+        &self,
+        directive: &Directive<'_>,
+        w: &mut W,
+    ) -> Result<(), BasicRendererError> {
+        let mut unsigned = directive.clone();
+        if let Some(meta) = directive_meta_mut(&mut unsigned) {
+            meta.remove("checksum");
+        }
+        let checksum = format!("{:016x}", fnv1a(self.render_to_string(&unsigned)?.as_bytes()));
+        let mut signed = unsigned.clone();
+        if let Some(meta) = directive_meta_mut(&mut signed) {
+            meta.insert("checksum", &checksum);
+        }
+        self.render(&signed, w)
+    }
+
+    fn sorted_postings<'a, 'b>(&self, postings: &'a [Posting<'b>]) -> Vec<&'a Posting<'b>> {
+        match self.posting_sort {
+            PostingSort::Preserve => postings.iter().collect(),
+            PostingSort::ElidedLast => {
+                let mut postings: Vec<_> = postings.iter().collect();
+                postings.sort_by_key(|posting| posting.units.num.is_none());
+                postings
+            }
+        }
+    }
+
+    fn write_keyword<W: Write>(
+        &self,
+        w: &mut W,
+        date: &dyn Display,
+        keyword: &str,
+    ) -> Result<(), BasicRendererError> {
+        let head = format!("{} {}", self.format_date(date), keyword);
+        let padding = self
+            .keyword_column
+            .map_or(1, |column| column.saturating_sub(head.chars().count()).max(1));
+        write!(w, "{}{:padding$}", head, "", padding = padding)?;
+        Ok(())
+    }
+
+    fn format_date(&self, date: &dyn Display) -> String {
+        let separator = match self.date_format {
+            DateFormat::Dashes => '-',
+            DateFormat::Slashes => '/',
+        };
+        // Always zero-pad, whatever the `Display` of the underlying date type does.
+        match date_parts(date) {
+            Some((year, month, day)) => format!(
+                "{:04}{}{:02}{}{:02}",
+                year, separator, month, separator, day
+            ),
+            None => date.to_string().replace(['-', '/'], &separator.to_string()),
+        }
+    }
+
+    fn write_date<W: Write>(&self, w: &mut W, date: &dyn Display) -> Result<(), BasicRendererError> {
+        write!(w, "{}", self.format_date(date))?;
+        Ok(())
+    }
+
+    fn write_number<W: Write>(
+        &self,
+        w: &mut W,
+        num: &Decimal,
+        currency: Option<&str>,
+    ) -> Result<(), BasicRendererError> {
+        let precision = currency
+            .and_then(|currency| self.currency_precision.get(&*self.currency(currency)))
+            .or(self.default_precision.as_ref());
+        let number = match precision {
+            Some(&precision) => {
+                // Round half away from zero, as people do by hand, rather than to even.
+                let mut rounded =
+                    num.round_dp_with_strategy(precision, RoundingStrategy::MidpointAwayFromZero);
+                // A small negative number should not render as `-0.00`.
+                if rounded.is_zero() {
+                    rounded = rounded.abs();
+                }
+                format!("{:.*}", precision as usize, rounded)
+            }
+            None => num.to_string(),
+        };
+        // Numbers that render as zero stay unsigned.
+        let positive =
+            !number.starts_with('-') && number.contains(|c: char| c.is_ascii_digit() && c != '0');
+        if self.explicit_sign && positive {
+            write!(w, "+")?;
+        }
+        match self.group_digits {
+            Some(separator) => write!(w, "{}", group_digits(&number, separator))?,
+            None => write!(w, "{}", number)?,
+        }
+        Ok(())
+    }
+
+    /// Write an amount as `num CCY`, or as `num ~ tolerance CCY` when a tolerance is given.
+    fn write_amount_with_tolerance<W: Write>(
+        &self,
+        w: &mut W,
+        amount: &Amount<'_>,
+        tolerance: Option<&Decimal>,
+    ) -> Result<(), BasicRendererError> {
+        self.write_amount_number(w, amount, tolerance)?;
+        self.write_unit_currency(w, amount.currency)?;
+        Ok(())
+    }
+
+    /// Write the number of an amount, followed by ` ~ tolerance` when a tolerance is given.
+    fn write_amount_number<W: Write>(
+        &self,
+        w: &mut W,
+        amount: &Amount<'_>,
+        tolerance: Option<&Decimal>,
+    ) -> Result<(), BasicRendererError> {
+        self.write_number(w, &amount.num, Some(amount.currency))?;
+        if let Some(tolerance) = tolerance {
+            write!(w, " ~ ")?;
+            self.write_number(w, tolerance, Some(amount.currency))?;
+        }
+        Ok(())
+    }
+
+    /// The currency as it should appear in the output.
+    fn currency<'c>(&'c self, currency: &'c str) -> Cow<'c, str> {
+        let currency = self
+            .currency_rename
+            .get(currency)
+            .map_or(currency, String::as_str);
+        if self.uppercase_currencies {
+            Cow::Owned(currency.to_uppercase())
+        } else {
+            Cow::Borrowed(currency)
+        }
+    }
+
+    fn currency_symbol(&self, currency: &str) -> Option<&str> {
+        if self.display_mode {
+            self.currency_symbols.get(currency).map(String::as_str)
+        } else {
+            None
+        }
+    }
+
+    fn currency_display<'c>(&'c self, currency: &'c str) -> Cow<'c, str> {
+        let currency = self.currency(currency);
+        match self.currency_symbol(&currency) {
+            Some(symbol) => Cow::Borrowed(symbol),
+            None => currency,
+        }
+    }
+
+    /// Write the currency following a number.
+    fn write_unit_currency<W: Write>(
+        &self,
+        w: &mut W,
+        currency: &str,
+    ) -> Result<(), BasicRendererError> {
+        let currency = self.currency(currency);
+        match self.currency_symbol(&currency) {
+            Some(symbol) => write!(w, "{}", symbol)?,
+            None => write!(w, " {}", currency)?,
+        }
+        Ok(())
+    }
+
+    /// Write `s` with tabs handled according to `tab_escape` and newlines escaped, so a value can
+    /// never introduce a line or indentation of its own. Inside quoted strings, quotes and
+    /// backslashes are escaped as well, so the output parses back to the same string.
+    fn write_escaped<W: Write>(
+        &self,
+        w: &mut W,
+        s: &str,
+        quoted: bool,
+    ) -> Result<(), BasicRendererError> {
+        let mut start = 0;
+        for (i, c) in s.char_indices() {
+            let escaped = match c {
+                '\t' => match self.tab_escape {
+                    TabEscape::Escape => "\\t",
+                    TabEscape::Spaces => " ",
+                },
+                '"' if quoted => "\\\"",
+                '\\' if quoted => "\\\\",
+                '\n' => "\\n",
+                _ => continue,
+            };
+            write!(w, "{}{}", &s[start..i], escaped)?;
+            start = i + c.len_utf8();
+        }
+        write!(w, "{}", &s[start..])?;
+        Ok(())
+    }
+
+    fn write_quoted_str<W: Write>(&self, w: &mut W, s: &str) -> Result<(), BasicRendererError> {
+        write!(w, "\"")?;
+        self.write_escaped(w, s, true)?;
+        write!(w, "\"")?;
+        Ok(())
+    }
+
+    /// Write a metadata value or `custom` argument, quoted unless `infer_value_types` finds it is
+    /// not a string.
+    fn write_value<W: Write>(&self, w: &mut W, value: &str) -> Result<(), BasicRendererError> {
+        if self.infer_value_types && is_bare_value(value) {
+            self.write_escaped(w, value, false)
+        } else {
+            self.write_quoted_str(w, value)
+        }
+    }
+
+    fn newline<W: Write>(&self, w: &mut W) -> Result<(), BasicRendererError> {
+        match self.line_ending {
+            LineEnding::Lf => writeln!(w)?,
+            LineEnding::Crlf => w.write_all(b"\r\n")?,
+        }
+        Ok(())
+    }
+
+    /// The width of one level of indentation, counting a tab as a single character.
+    fn indent_width(&self) -> usize {
+        match self.indent {
+            Indent::Tab => 1,
+            Indent::Spaces(n) => n,
+        }
+    }
+
+    fn write_indent<W: Write>(&self, w: &mut W, depth: usize) -> Result<(), BasicRendererError> {
+        for _ in 0..depth {
+            match self.indent {
+                Indent::Tab => write!(w, "\t")?,
+                Indent::Spaces(n) => write!(w, "{:n$}", "", n = n)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Render metadata entries, each on its own line indented `depth` levels.
+    fn render_key_value<W: Write>(
+        &self,
+        w: &mut W,
+        kv: &HashMap<&str, &str>,
+        depth: usize,
+    ) -> Result<(), BasicRendererError> {
+        if !self.render_meta {
+            return Ok(());
+        }
+        let mut entries: Vec<_> = kv.iter().collect();
+        match self.meta_order {
+            // `HashMap` iteration order differs between runs, so sort to keep the output stable.
+            // The core does not remember the parsed order, so `AsParsed` falls back to sorting
+            // until metadata is stored in an order-preserving map such as an `IndexMap`.
+            MetaOrder::Sorted | MetaOrder::AsParsed => entries.sort_by_key(|(key, _)| *key),
+        }
+        for (key, value) in entries {
+            self.write_indent(w, depth)?;
+            write!(w, "{}:", key)?;
+            if value.is_empty() {
+                if self.empty_meta_value == EmptyMetaValue::EmptyQuoted {
+                    write!(w, " \"\"")?;
+                }
+            } else {
+                write!(w, " ")?;
+                self.write_value(w, value)?;
+            }
+            self.newline(w)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn render<W: Write>(w: &mut W, document: &Document<'_>) -> Result<(), BasicRendererError>{
+    BasicRenderer::default().render(document, w)
+}
+
+/// Render a document into a `String` with the default renderer.
+///
+/// ```
+/// # use beancount::core::Directive;
+/// # fn main() -> Result<(), beancount_render::BasicRendererError> {
+/// let source = "2019-01-01 document Assets:Cash \"receipt.pdf\"\n";
+/// let ledger = beancount::parse(source).expect("valid ledger");
+/// let document = match &ledger.directives[0] {
+///     Directive::Document(document) => document,
+///     _ => unreachable!(),
+/// };
+/// let rendered = beancount_render::render_to_string(document)?;
+/// assert_eq!(rendered, source);
+/// # Ok(())
+/// # }
+/// ```
+pub fn render_to_string(document: &Document<'_>) -> Result<String, BasicRendererError> {
+    BasicRenderer::default().render_to_string(document)
+}
+
+/// Displays a document the way `render_to_string` renders it, as in
+/// `println!("{}", Rendered(&document))`.
+#[derive(Copy, Clone, Debug)]
+pub struct Rendered<'a>(pub &'a Document<'a>);
+
+impl Display for Rendered<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        BasicRenderer::default()
+            .render_fmt(self.0, f)
+            .map_err(|_| fmt::Error)
+    }
+}
+
+/// Render a single directive into a `String` with the default renderer, without the blank line
+/// that separates the directives of a ledger.
+///
+/// ```
+/// # fn main() -> Result<(), beancount_render::BasicRendererError> {
+/// let ledger = beancount::parse("2019-01-01 open Assets:Cash USD\n").expect("valid ledger");
+/// let rendered = beancount_render::render_directive_to_string(&ledger.directives[0])?;
+/// assert_eq!(rendered, "2019-01-01 open Assets:Cash USD\n");
+/// # Ok(())
+/// # }
+/// ```
+pub fn render_directive_to_string(
+    directive: &Directive<'_>,
+) -> Result<String, BasicRendererError> {
+    BasicRenderer::default().render_directive_to_string(directive)
+}
+
+#[derive(Error, Debug)]
+pub enum BasicRendererError {
+    #[error("an io error occurred")]
+    Io(#[from] io::Error),
+    #[error("a formatting error occurred")]
+    Fmt(#[from] fmt::Error),
+    #[error(
+        "could not render unsupported directive{}",
+        index.map_or_else(String::new, |index| format!(" at index {}", index))
+    )]
+    Unsupported {
+        /// The index of the directive in the ledger, when rendering one.
+        index: Option<usize>,
+    },
+    #[error("invalid currency `{0}`")]
+    InvalidCurrency(String),
+    #[error("invalid transaction flag `{0}`")]
+    InvalidTransactionFlag(String),
+    #[error("invalid posting flag `{0}`")]
+    InvalidPostingFlag(String),
+    #[error("could not parse included file {}: {message}", path.display())]
+    Parse { path: PathBuf, message: String },
+    #[error("{} includes itself", .0.display())]
+    IncludeCycle(PathBuf),
+    #[error("rendered output was not valid utf-8")]
+    Utf8(#[from] FromUtf8Error),
+}
+
+pub trait Renderer<T, W: Write> {
+    type Error;
+    fn render(&self, renderable: T, write: &mut W) -> Result<(), Self::Error>;
+}
+
+impl<'a, W: Write> Renderer<&'a Ledger<'_>, W> for BasicRenderer {
     type Error = BasicRendererError;
-    fn render(&self, incomplete_amount: &'a IncompleteAmount<'_>, w: &mut W) -> Result<(), Self::Error> {
-        match (&incomplete_amount.num, &incomplete_amount.currency) {
-            (Some(num), Some(currency)) => write!(w, "{} {}", num, currency),
-            (None, Some(currency)) => write!(w, "{}", currency),
-            (Some(num), None) => write!(w, "{}", num),
-            _ => write!(w, ""),
-        }?;
+    fn render(&self, ledger: &'a Ledger<'_>, write: &mut W) -> Result<(), Self::Error> {
+        if self.sort_by_date {
+            let mut directives = ledger.directives.clone();
+            directives.sort_by_key(|directive| directive_date(directive).and_then(date_parts));
+            return self.render_directives(&directives, write);
+        }
+        self.render_directives(&ledger.directives, write)
+    }
+}
+
+impl<'a, W: Write> Renderer<&'a Document<'_>, W> for BasicRenderer {
+    type Error = BasicRendererError;
+    fn render(&self, document: &'a Document<'_>, write: &mut W) -> Result<(), Self::Error> {
+        self.write_keyword(write, &document.date, "document")?;
+        self.render(&document.account, write)?;
+        let path = match &self.document_base {
+            Some(base) => match Path::new(document.path).strip_prefix(base) {
+                Ok(relative) => relative.to_string_lossy(),
+                Err(_) => Cow::Borrowed(document.path),
+            },
+            None => Cow::Borrowed(document.path),
+        };
+        write!(write, " ")?;
+        self.write_quoted_str(write, &path)?;
+        write_tags_links(write, &document.tags, &document.links)?;
+        self.newline(write)?;
+        self.render_key_value(write, &document.meta, 1)?;
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> Renderer<&'a Directive<'_>, W> for BasicRenderer {
+    type Error = BasicRendererError;
+    fn render(&self, directive: &'a Directive<'_>, write: &mut W) -> Result<(), Self::Error> {
+        use Directive::*;
+        match directive {
+            Open(open) => self.render(open, write),
+            Close(close) => self.render(close, write),
+            Balance(balance) => self.render(balance, write),
+            Option(bc_option) => self.render(bc_option, write),
+            Commodity(commodity) => self.render(commodity, write),
+            Custom(custom) => self.render(custom, write),
+            Document(document) => self.render(document, write),
+            Event(event) => self.render(event, write),
+            Include(include) => self.render(include, write),
+            Note(note) => self.render(note, write),
+            Pad(pad) => self.render(pad, write),
+            Plugin(plugin) => self.render(plugin, write),
+            Price(price) => self.render(price, write),
+            Query(query) => self.render(query, write),
+            // The core has no `pushtag` or `poptag` directives: the parser adds the pushed tags to
+            // every transaction in between, so they are rendered as tags of those transactions.
+            Transaction(transaction) => self.render(transaction, write),
+            Unsupported if self.skip_unsupported => Ok(()),
+            Unsupported => Err(BasicRendererError::Unsupported { index: None }),
+        }
+    }
+}
+
+fn account_type_name(ty: &AccountType) -> &'static str {
+    match ty {
+        AccountType::Assets => "Assets",
+        AccountType::Liabilities => "Liabilities",
+        AccountType::Equity => "Equity",
+        AccountType::Income => "Income",
+        AccountType::Expenses => "Expenses",
+    }
+}
+
+fn account_key(account: &Account<'_>) -> String {
+    format!("{}:{}", account_type_name(&account.ty), account.parts.join(":"))
+}
+
+/// The year, month and day of a date, used to compare dates.
+fn date_parts(date: &dyn Display) -> Option<(i32, u32, u32)> {
+    let date = date.to_string();
+    let mut parts = date.split(['-', '/']);
+    let year = parts.next()?.parse().ok()?;
+    let month = parts.next()?.parse().ok()?;
+    let day = parts.next()?.parse().ok()?;
+    Some((year, month, day))
+}
+
+fn directive_date<'a>(directive: &'a Directive<'_>) -> Option<&'a dyn Display> {
+    match directive {
+        Directive::Open(open) => Some(&open.date),
+        Directive::Close(close) => Some(&close.date),
+        Directive::Balance(balance) => Some(&balance.date),
+        Directive::Commodity(commodity) => Some(&commodity.date),
+        Directive::Custom(custom) => Some(&custom.date),
+        Directive::Document(document) => Some(&document.date),
+        Directive::Event(event) => Some(&event.date),
+        Directive::Note(note) => Some(&note.date),
+        Directive::Pad(pad) => Some(&pad.date),
+        Directive::Price(price) => Some(&price.date),
+        Directive::Query(query) => Some(&query.date),
+        Directive::Transaction(transaction) => Some(&transaction.date),
+        Directive::Option(_)
+        | Directive::Include(_)
+        | Directive::Plugin(_)
+        | Directive::Unsupported => None,
+    }
+}
+
+/// The metadata of a directive, for those that have it.
+fn directive_meta_mut<'a, 'b>(
+    directive: &'a mut Directive<'b>,
+) -> Option<&'a mut HashMap<&'b str, &'b str>> {
+    match directive {
+        Directive::Open(open) => Some(&mut open.meta),
+        Directive::Close(close) => Some(&mut close.meta),
+        Directive::Balance(balance) => Some(&mut balance.meta),
+        Directive::Commodity(commodity) => Some(&mut commodity.meta),
+        Directive::Custom(custom) => Some(&mut custom.meta),
+        Directive::Document(document) => Some(&mut document.meta),
+        Directive::Event(event) => Some(&mut event.meta),
+        Directive::Note(note) => Some(&mut note.meta),
+        Directive::Pad(pad) => Some(&mut pad.meta),
+        Directive::Price(price) => Some(&mut price.meta),
+        Directive::Query(query) => Some(&mut query.meta),
+        Directive::Transaction(transaction) => Some(&mut transaction.meta),
+        Directive::Option(_)
+        | Directive::Include(_)
+        | Directive::Plugin(_)
+        | Directive::Unsupported => None,
+    }
+}
+
+/// The day after `date`, formatted as a beancount date.
+fn format_next_day(date: (i32, u32, u32)) -> String {
+    let (year, month, day) = next_day(date);
+    format!("{}-{:02}-{:02}", year, month, day)
+}
+
+/// The day after a date.
+fn next_day((year, month, day): (i32, u32, u32)) -> (i32, u32, u32) {
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days = match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    };
+    if day < days {
+        (year, month, day + 1)
+    } else if month < 12 {
+        (year, month + 1, 1)
+    } else {
+        (year + 1, 1, 1)
+    }
+}
+
+/// The accounts posted to by transactions, with the currencies posted to each, grouped by the
+/// index of the last transaction posting to them.
+fn last_postings<'a, 'b>(
+    directives: &'a [Directive<'b>],
+) -> HashMap<usize, Vec<(&'a Account<'b>, Vec<&'b str>)>> {
+    let mut last: BTreeMap<String, (usize, &'a Account<'b>, Vec<&'b str>)> = BTreeMap::new();
+    for (i, directive) in directives.iter().enumerate() {
+        if let Directive::Transaction(transaction) = directive {
+            for posting in &transaction.postings {
+                let entry = last
+                    .entry(account_key(&posting.account))
+                    .or_insert((i, &posting.account, Vec::new()));
+                entry.0 = i;
+                if let Some(currency) = posting.units.currency {
+                    if !entry.2.contains(&currency) {
+                        entry.2.push(currency);
+                    }
+                }
+            }
+        }
+    }
+    let mut grouped: HashMap<usize, Vec<_>> = HashMap::new();
+    for (i, account, currencies) in last.into_values() {
+        grouped.entry(i).or_default().push((account, currencies));
+    }
+    grouped
+}
+
+fn directive_accounts<'a, 'b>(directive: &'a Directive<'b>) -> Vec<&'a Account<'b>> {
+    match directive {
+        Directive::Pad(pad) => vec![&pad.pad_to_account, &pad.pad_from_account],
+        Directive::Transaction(transaction) => transaction
+            .postings
+            .iter()
+            .map(|posting| &posting.account)
+            .collect(),
+        _ => primary_account(directive).into_iter().collect(),
+    }
+}
+
+/// Write tags followed by links, each preceded by a space. Empty entries are skipped so they
+/// cannot leave trailing whitespace.
+fn write_tags_links<W: Write>(
+    w: &mut W,
+    tags: &[&str],
+    links: &[&str],
+) -> Result<(), BasicRendererError> {
+    for tag_or_link in tags.iter().chain(links) {
+        if !tag_or_link.is_empty() {
+            write!(w, " {}", tag_or_link)?;
+        }
+    }
+    Ok(())
+}
+
+/// The width in characters of the integer part of a rendered number, and of its decimal point and
+/// fraction.
+fn number_widths(number: &str) -> (usize, usize) {
+    let width = number.chars().count();
+    let integer = number.split('.').next().map_or(0, |integer| integer.chars().count());
+    (integer, width - integer)
+}
+
+/// Insert `separator` between every group of three digits of the integer part of `number`.
+fn group_digits(number: &str, separator: char) -> String {
+    let (sign, number) = match number.strip_prefix('-') {
+        Some(number) => ("-", number),
+        None => ("", number),
+    };
+    let (integer, fraction) = number.split_at(number.find('.').unwrap_or(number.len()));
+    let mut grouped = String::from(sign);
+    for (i, digit) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            grouped.push(separator);
+        }
+        grouped.push(digit);
+    }
+    grouped.push_str(fraction);
+    grouped
+}
+
+/// The 64-bit FNV-1a hash of `bytes`.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn transaction_payee<'a>(directive: &Directive<'a>) -> Option<&'a str> {
+    match directive {
+        Directive::Transaction(transaction) => transaction.payee,
+        _ => None,
+    }
+}
+
+/// The currencies of the posting units of every account.
+fn posting_currencies<'a>(directives: &[Directive<'a>]) -> HashMap<String, HashSet<&'a str>> {
+    let mut currencies: HashMap<String, HashSet<&'a str>> = HashMap::new();
+    for directive in directives {
+        if let Directive::Transaction(transaction) = directive {
+            for posting in &transaction.postings {
+                if let Some(currency) = posting.units.currency {
+                    currencies
+                        .entry(account_key(&posting.account))
+                        .or_default()
+                        .insert(currency);
+                }
+            }
+        }
+    }
+    currencies
+}
+
+/// Accounts that are used without being opened, together with the earliest date they are used on.
+fn missing_opens<'a, 'b>(
+    directives: &'a [Directive<'b>],
+) -> Vec<(&'a dyn Display, &'a Account<'b>)> {
+    let mut opened = HashSet::new();
+    let mut used: HashMap<String, (&'a dyn Display, &'a Account<'b>)> = HashMap::new();
+    for directive in directives {
+        if let Directive::Open(open) = directive {
+            opened.insert(account_key(&open.account));
+            continue;
+        }
+        let date = match directive_date(directive) {
+            Some(date) => date,
+            None => continue,
+        };
+        for account in directive_accounts(directive) {
+            let earliest = used.entry(account_key(account)).or_insert((date, account));
+            if date_parts(date) < date_parts(earliest.0) {
+                *earliest = (date, account);
+            }
+        }
+    }
+    let mut missing: Vec<_> = used
+        .into_iter()
+        .filter(|(key, _)| !opened.contains(key))
+        .map(|(key, (date, account))| (date_parts(date), key, date, account))
+        .collect();
+    missing.sort_by(|a, b| (a.0, &a.1).cmp(&(b.0, &b.1)));
+    missing
+        .into_iter()
+        .map(|(_, _, date, account)| (date, account))
+        .collect()
+}
+
+/// Whether beancount reads `flag` back as a flag: one of `&`, `#`, `?` and `%`, or a single
+/// uppercase letter.
+fn is_valid_flag(flag: &str) -> bool {
+    matches!(flag, "&" | "#" | "?" | "%")
+        || (flag.len() == 1 && flag.chars().all(|c| c.is_ascii_uppercase()))
+}
+
+/// Whether `currency` is a valid beancount commodity symbol: up to 24 characters, starting with an
+/// uppercase letter and ending with an uppercase letter or digit, with `'`, `.`, `_` and `-` also
+/// allowed in between.
+fn is_valid_currency(currency: &str) -> bool {
+    let bytes = currency.as_bytes();
+    match (bytes.first(), bytes.last()) {
+        (Some(first), Some(last)) => {
+            bytes.len() <= 24
+                && first.is_ascii_uppercase()
+                && (last.is_ascii_uppercase() || last.is_ascii_digit())
+                && bytes.iter().all(|&b| {
+                    b.is_ascii_uppercase() || b.is_ascii_digit() || b"'._-".contains(&b)
+                })
+        }
+        _ => false,
+    }
+}
+
+/// Whether a value looks like a number, date, `TRUE` or `FALSE`, account, currency or amount, and
+/// is written without quotes when `infer_value_types` is set.
+fn is_bare_value(value: &str) -> bool {
+    // Grouping commas are only allowed between groups of three digits, and a decimal point has
+    // to be followed by a digit.
+    fn is_number(s: &str) -> bool {
+        let s = s.strip_prefix('-').unwrap_or(s);
+        let (integer, fraction) = match s.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (s, None),
+        };
+        let digits = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+        let mut groups = integer.split(',');
+        let first = groups.next().unwrap_or_default();
+        digits(first)
+            && (!integer.contains(',') || first.len() <= 3)
+            && groups.all(|group| group.len() == 3 && digits(group))
+            && fraction.is_none_or(digits)
+    }
+    let is_date = value.chars().all(|c| c.is_ascii_digit() || c == '-' || c == '/')
+        && value.split(['-', '/']).count() == 3
+        && date_parts(&value)
+            .is_some_and(|(_, month, day)| (1..=12).contains(&month) && (1..=31).contains(&day));
+    let is_account = !value.contains(char::is_whitespace) && {
+        let mut components = value.split(':');
+        let root = components.next().unwrap_or_default();
+        ["Assets", "Liabilities", "Equity", "Income", "Expenses"].contains(&root)
+            && value.contains(':')
+            && components.all(|component| {
+                component.starts_with(|c: char| c.is_ascii_uppercase())
+                    && component.chars().all(|c| c.is_alphanumeric() || c == '-')
+            })
+    };
+    let is_amount = value
+        .split_once(' ')
+        .is_some_and(|(num, currency)| is_number(num) && is_valid_currency(currency));
+    is_number(value)
+        || is_date
+        || value == "TRUE"
+        || value == "FALSE"
+        || is_account
+        || is_valid_currency(value)
+        || is_amount
+}
+
+fn primary_account<'a, 'b>(directive: &'a Directive<'b>) -> Option<&'a Account<'b>> {
+    match directive {
+        Directive::Open(open) => Some(&open.account),
+        Directive::Close(close) => Some(&close.account),
+        Directive::Balance(balance) => Some(&balance.account),
+        Directive::Document(document) => Some(&document.account),
+        Directive::Note(note) => Some(&note.account),
+        Directive::Pad(pad) => Some(&pad.pad_to_account),
+        Directive::Transaction(transaction) => transaction
+            .postings
+            .first()
+            .map(|posting| &posting.account),
+        _ => None,
+    }
+}
+
+impl<'a, W: Write> Renderer<&'a Open<'_>, W> for BasicRenderer {
+    type Error = BasicRendererError;
+    fn render(&self, open: &'a Open<'_>, write: &mut W) -> Result<(), Self::Error> {
+        self.write_keyword(write, &open.date, "open")?;
+        self.render(&open.account, write)?;
+        // Only separate the currencies from the account when there are any, so there is never a
+        // trailing space.
+        for (i, currency) in open.currencies.iter().enumerate() {
+            write!(write, "{}{}", if i == 0 { " " } else { "," }, self.currency(currency))?;
+        }
+        match open.booking {
+            Booking::Strict => write!(write, r#" "STRICT""#)?,
+            Booking::None if self.explicit_booking_none => write!(write, r#" "NONE""#)?,
+            Booking::None => {}
+            Booking::Average => write!(write, r#" "AVERAGE""#)?,
+            Booking::Fifo => write!(write, r#" "FIFO""#)?,
+            Booking::Lifo => write!(write, r#" "LIFO""#)?,
+        };
+        self.newline(write)?;
+        self.render_key_value(write, &open.meta, 1)?;
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> Renderer<&'a Close<'_>, W> for BasicRenderer {
+    type Error = BasicRendererError;
+    fn render(&self, close: &'a Close<'_>, write: &mut W) -> Result<(), Self::Error> {
+        self.write_keyword(write, &close.date, "close")?;
+        self.render(&close.account, write)?;
+        self.newline(write)?;
+        self.render_key_value(write, &close.meta, 1)?;
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> Renderer<&'a Account<'_>, W> for BasicRenderer {
+    type Error = BasicRendererError;
+    fn render(&self, account: &'a Account<'_>, write: &mut W) -> Result<(), Self::Error> {
+        let account = match self.account_rewriter {
+            Some(rewrite) => rewrite(account),
+            None => Cow::Borrowed(account),
+        };
+        let mut parts = &account.parts[..];
+        if let Some(depth) = self.max_account_depth.filter(|_| self.display_mode) {
+            // The root counts as the first level.
+            parts = &parts[..parts.len().min(depth.saturating_sub(1))];
+        }
+        write!(write, "{}", account_type_name(&account.ty))?;
+        if !parts.is_empty() {
+            write!(write, ":{}", parts.join(":"))?;
+        }
         Ok(())
     }
 }
+
+impl<'a, W: Write> Renderer<&'a Balance<'_>, W> for BasicRenderer {
+    type Error = BasicRendererError;
+    fn render(&self, balance: &'a Balance<'_>, w: &mut W) -> Result<(), Self::Error> {
+        self.write_balance(
+            w,
+            &balance.date,
+            &balance.account,
+            &balance.amount,
+            balance.tolerance.as_ref(),
+        )?;
+        self.newline(w)?;
+        self.render_key_value(w, &balance.meta, 1)?;
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> Renderer<&'a Amount<'_>, W> for BasicRenderer {
+    type Error = BasicRendererError;
+    fn render(&self, amount: &'a Amount<'_>, w: &mut W) -> Result<(), Self::Error> {
+        self.write_amount_with_tolerance(w, amount, None)
+    }
+}
+
+impl<'a, W: Write> Renderer<&'a BcOption<'_>, W> for BasicRenderer {
+    type Error = BasicRendererError;
+    fn render(&self, option: &'a BcOption<'_>, w: &mut W) -> Result<(), Self::Error> {
+        // The grammar only accepts strings here, so numeric and boolean options such as
+        // `inferred_tolerance_multiplier` are quoted as well; beancount converts them itself.
+        write!(w, "option ")?;
+        self.write_quoted_str(w, option.name)?;
+        write!(w, " ")?;
+        self.write_quoted_str(w, option.val)?;
+        self.newline(w)?;
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> Renderer<&'a Commodity<'_>, W> for BasicRenderer {
+    type Error = BasicRendererError;
+    fn render(&self, commodity: &'a Commodity<'_>, w: &mut W) -> Result<(), Self::Error> {
+        let name = self.currency(commodity.name);
+        if self.strict && !is_valid_currency(&name) {
+            return Err(BasicRendererError::InvalidCurrency(name.into_owned()));
+        }
+        self.write_keyword(w, &commodity.date, "commodity")?;
+        write!(w, "{}", name)?;
+        self.newline(w)?;
+        self.render_key_value(w, &commodity.meta, 1)
+    }
+}
+
+impl<'a, W: Write> Renderer<&'a Custom<'_>, W> for BasicRenderer {
+    type Error = BasicRendererError;
+    fn render(&self, custom: &'a Custom<'_>, w: &mut W) -> Result<(), Self::Error> {
+        self.write_keyword(w, &custom.date, "custom")?;
+        self.write_quoted_str(w, custom.name)?;
+        for arg in &custom.args {
+            write!(w, " ")?;
+            self.write_value(w, arg)?;
+        }
+        self.newline(w)?;
+        self.render_key_value(w, &custom.meta, 1)
+    }
+}
+
+impl<'a, W: Write> Renderer<&'a Event<'_>, W> for BasicRenderer {
+    type Error = BasicRendererError;
+    fn render(&self, event: &'a Event<'_>, w: &mut W) -> Result<(), Self::Error> {
+        self.write_keyword(w, &event.date, "event")?;
+        self.write_quoted_str(w, event.name)?;
+        write!(w, " ")?;
+        self.write_quoted_str(w, event.description)?;
+        self.newline(w)?;
+        self.render_key_value(w, &event.meta, 1)
+    }
+}
+
+impl<'a, W: Write> Renderer<&'a Include<'_>, W> for BasicRenderer {
+    type Error = BasicRendererError;
+    fn render(&self, include: &'a Include<'_>, w: &mut W) -> Result<(), Self::Error> {
+        write!(w, "include ")?;
+        self.write_quoted_str(w, include.filename)?;
+        self.newline(w)?;
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> Renderer<&'a Note<'_>, W> for BasicRenderer {
+    type Error = BasicRendererError;
+    fn render(&self, note: &'a Note<'_>, w: &mut W) -> Result<(), Self::Error> {
+        self.write_keyword(w, &note.date, "note")?;
+        self.render(&note.account, w)?;
+        write!(w, " ")?;
+        self.write_quoted_str(w, note.comment)?;
+        write_tags_links(w, &note.tags, &note.links)?;
+        self.newline(w)?;
+        self.render_key_value(w, &note.meta, 1)
+    }
+}
+
+impl<'a, W: Write> Renderer<&'a Pad<'_>, W> for BasicRenderer {
+    type Error = BasicRendererError;
+    fn render(&self, pad: &'a Pad<'_>, w: &mut W) -> Result<(), Self::Error> {
+        self.write_keyword(w, &pad.date, "pad")?;
+        // `pad <account> <source>`: the padded account comes first and the account the padding
+        // is taken from second.
+        self.render(&pad.pad_to_account, w)?;
+        write!(w, " ")?;
+        self.render(&pad.pad_from_account, w)?;
+        self.newline(w)?;
+        self.render_key_value(w, &pad.meta, 1)
+    }
+}
+
+impl<'a, W: Write> Renderer<&'a Plugin<'_>, W> for BasicRenderer {
+    type Error = BasicRendererError;
+    fn render(&self, plugin: &'a Plugin<'_>, w: &mut W) -> Result<(), Self::Error> {
+        write!(w, "plugin ")?;
+        self.write_quoted_str(w, plugin.module)?;
+        if let Some(config) = &plugin.config {
+            write!(w, " ")?;
+            self.write_quoted_str(w, config)?;
+        }
+        self.newline(w)?;
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> Renderer<&'a Price<'_>, W> for BasicRenderer {
+    type Error = BasicRendererError;
+    fn render(&self, price: &'a Price<'_>, w: &mut W) -> Result<(), Self::Error> {
+        self.write_keyword(w, &price.date, "price")?;
+        write!(w, "{} ", self.currency(price.currency))?;
+        self.render(&price.amount, w)?;
+        self.newline(w)?;
+        self.render_key_value(w, &price.meta, 1)
+    }
+}
+
+impl<'a, W: Write> Renderer<&'a Query<'_>, W> for BasicRenderer {
+    type Error = BasicRendererError;
+    fn render(&self, query: &'a Query<'_>, w: &mut W) -> Result<(), Self::Error> {
+        self.write_keyword(w, &query.date, "query")?;
+        self.write_quoted_str(w, query.name)?;
+        write!(w, " ")?;
+        self.write_quoted_str(w, query.query_string)?;
+        self.newline(w)?;
+        self.render_key_value(w, &query.meta, 1)
+    }
+}
+
+impl<'a, W: Write> Renderer<&'a Transaction<'_>, W> for BasicRenderer {
+    type Error = BasicRendererError;
+    fn render(&self, transaction: &'a Transaction<'_>, w: &mut W) -> Result<(), Self::Error> {
+        if let Some(max) = self.max_postings_warn {
+            if transaction.postings.len() > max {
+                write!(w, "; WARNING: {} postings", transaction.postings.len())?;
+                self.newline(w)?;
+            }
+        }
+        if let Some(max) = self.narration_warn_len {
+            if transaction.narration.chars().count() > max {
+                write!(w, "; WARNING: long narration")?;
+                self.newline(w)?;
+            }
+        }
+        self.write_date(w, &transaction.date)?;
+        write!(w, " ")?;
+        // The core parses both an omitted flag and `txn` to `Flag::Okay`, so there is no way to
+        // tell a defaulted flag apart from an explicit `*` here; `flag_style` picks one for all.
+        if let Flag::Other(flag) = &transaction.flag {
+            let rejected = self.transaction_flag_policy == FlagPolicy::RejectOther;
+            if self.strict && (rejected || !is_valid_flag(flag)) {
+                return Err(BasicRendererError::InvalidTransactionFlag(flag.to_string()));
+            }
+        }
+        match (&transaction.flag, self.flag_style) {
+            (Flag::Okay, FlagStyle::Txn) => write!(w, "txn")?,
+            (flag, _) => self.render(flag, w)?,
+        }
+        if let Some(payee) = transaction.payee {
+            let payee = self.payee_rename.get(payee).map_or(payee, String::as_str);
+            write!(w, " ")?;
+            self.write_quoted_str(w, payee)?;
+        }
+        let mut narration = transaction.narration;
+        if let Some(placeholder) = &self.narration_placeholder {
+            if narration.is_empty() && transaction.payee.unwrap_or_default().is_empty() {
+                narration = placeholder;
+            }
+        }
+        write!(w, " ")?;
+        self.write_quoted_str(w, narration)?;
+        write_tags_links(w, &transaction.tags, &transaction.links)?;
+        self.newline(w)?;
+        // Metadata directly below the header belongs to the transaction; below a posting it would
+        // belong to that posting.
+        let (comments, meta): (HashMap<_, _>, HashMap<_, _>) = transaction
+            .meta
+            .iter()
+            .map(|(key, value)| (*key, *value))
+            .partition(|(key, _)| self.meta_comment_keys.contains(*key));
+        self.render_key_value(w, &meta, 1)?;
+        if self.render_meta {
+            let mut comments: Vec<_> = comments.into_iter().collect();
+            comments.sort_unstable();
+            for (key, value) in comments {
+                self.write_indent(w, 1)?;
+                write!(w, "; {}: ", key)?;
+                self.write_escaped(w, value, false)?;
+                self.newline(w)?;
+            }
+        }
+        let mut currencies = transaction.postings.iter().map(|posting| posting.units.currency);
+        let same_currency = match currencies.next() {
+            Some(first @ Some(_)) => currencies.all(|currency| currency == first),
+            _ => false,
+        };
+        let elide_currencies = self.display_mode && self.elide_currencies && same_currency;
+        let columns = self.posting_columns(&transaction.postings)?;
+        for (i, posting) in self.sorted_postings(&transaction.postings).into_iter().enumerate() {
+            let layout = PostingLayout {
+                elide_currency: elide_currencies && i > 0,
+                ..columns
+            };
+            self.render_posting(posting, &layout, w)?;
+        }
+        if self.net_comment {
+            let mut net: BTreeMap<&str, Decimal> = BTreeMap::new();
+            for posting in &transaction.postings {
+                if let (Some(num), Some(currency)) = (posting.units.num, posting.units.currency) {
+                    *net.entry(currency).or_default() += num;
+                }
+            }
+            write!(w, "; net:")?;
+            for (i, (currency, num)) in net.iter().enumerate() {
+                write!(w, "{}", if i == 0 { " " } else { ", " })?;
+                self.write_number(w, num, Some(currency))?;
+                self.write_unit_currency(w, currency)?;
+            }
+            self.newline(w)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> Renderer<&'a Posting<'_>, W> for BasicRenderer {
+    type Error = BasicRendererError;
+    fn render(&self, posting: &'a Posting<'_>, w: &mut W) -> Result<(), Self::Error> {
+        let layout = PostingLayout {
+            currency_column: self.align_currency,
+            ..PostingLayout::default()
+        };
+        self.render_posting(posting, &layout, w)
+    }
+}
+
+impl BasicRenderer {
+    /// Write a `balance` line without its newline, padding the amount so its currency lines up
+    /// with those of postings when `align_currency` is set.
+    fn write_balance<W: Write>(
+        &self,
+        w: &mut W,
+        date: &dyn Display,
+        account: &Account<'_>,
+        amount: &Amount<'_>,
+        tolerance: Option<&Decimal>,
+    ) -> Result<(), BasicRendererError> {
+        let (line, number) = self.balance_parts(date, account, amount, tolerance)?;
+        write!(w, "{}", line)?;
+        match self.align_currency {
+            Some(column) => {
+                // Line up with the currencies of postings, which are counted from the end of the
+                // indentation.
+                let column = column + self.indent_width();
+                let width = line.chars().count() + number.chars().count() + 1;
+                let padding = column.saturating_sub(width).max(1);
+                write!(w, "{:padding$}", "", padding = padding)?;
+            }
+            None => write!(w, "\t")?,
+        }
+        self.write_amount_with_tolerance(w, amount, tolerance)
+    }
+
+    /// The start of a `balance` line up to the account, and the number of its amount with the
+    /// tolerance, if any.
+    fn balance_parts(
+        &self,
+        date: &dyn Display,
+        account: &Account<'_>,
+        amount: &Amount<'_>,
+        tolerance: Option<&Decimal>,
+    ) -> Result<(String, String), BasicRendererError> {
+        let mut line = Vec::new();
+        self.write_keyword(&mut line, date, "balance")?;
+        self.render(account, &mut line)?;
+        let mut number = Vec::new();
+        self.write_amount_number(&mut number, amount, tolerance)?;
+        Ok((String::from_utf8(line)?, String::from_utf8(number)?))
+    }
+
+    /// The width of a `balance` line up to its currency, which follows the line, a space, the
+    /// number and another space.
+    fn balance_width(
+        &self,
+        date: &dyn Display,
+        account: &Account<'_>,
+        amount: &Amount<'_>,
+        tolerance: Option<&Decimal>,
+    ) -> Result<usize, BasicRendererError> {
+        let (line, number) = self.balance_parts(date, account, amount, tolerance)?;
+        Ok(line.chars().count() + 1 + number.chars().count() + 1)
+    }
+
+    /// The smallest currency column from `column` on that fits every posting and balance of
+    /// `directives`, including the placeholder balances of `trailing_balances`.
+    fn whole_file_column(
+        &self,
+        directives: &[Directive<'_>],
+        mut column: usize,
+    ) -> Result<usize, BasicRendererError> {
+        let trailing_balances = if self.trailing_balances {
+            last_postings(directives)
+        } else {
+            HashMap::new()
+        };
+        for (i, directive) in directives.iter().enumerate() {
+            let date = directive_date(directive).and_then(date_parts);
+            if let (Some(accounts), Some(date)) = (trailing_balances.get(&i), date) {
+                let date = format_next_day(date);
+                for (account, currencies) in accounts {
+                    for currency in currencies {
+                        let amount = Amount {
+                            num: Decimal::ZERO,
+                            currency,
+                        };
+                        let width = self.balance_width(&date, account, &amount, None)?;
+                        column = column.max(width.saturating_sub(self.indent_width()));
+                    }
+                }
+            }
+            match directive {
+                Directive::Transaction(transaction) => {
+                    let columns = self.posting_columns(&transaction.postings)?;
+                    column = column.max(columns.currency_column.unwrap_or(0));
+                }
+                Directive::Balance(balance) => {
+                    let width = self.balance_width(
+                        &balance.date,
+                        &balance.account,
+                        &balance.amount,
+                        balance.tolerance.as_ref(),
+                    )?;
+                    column = column.max(width.saturating_sub(self.indent_width()));
+                }
+                _ => {}
+            }
+        }
+        Ok(column)
+    }
+
+    /// The columns the postings are aligned to, following `align_currency` and `alignment`.
+    fn posting_columns(
+        &self,
+        postings: &[Posting<'_>],
+    ) -> Result<PostingLayout, BasicRendererError> {
+        let column = match self.align_currency {
+            Some(column) => column,
+            None => return Ok(PostingLayout::default()),
+        };
+        let mut widest = 0;
+        let mut widest_point = 0;
+        let mut widest_fraction = 0;
+        for posting in postings {
+            if let (head, Some(number)) = self.posting_head(posting)? {
+                let head = head.chars().count();
+                let (integer, fraction) = number_widths(&number);
+                widest = widest.max(head + integer + fraction);
+                widest_point = widest_point.max(head + integer);
+                widest_fraction = widest_fraction.max(fraction);
+            }
+        }
+        Ok(match self.alignment {
+            Alignment::Currency | Alignment::WholeFile => PostingLayout {
+                currency_column: Some(column.max(widest + 1)),
+                ..PostingLayout::default()
+            },
+            Alignment::DecimalPoint => PostingLayout {
+                currency_column: Some(column.max(widest_point + widest_fraction + 1)),
+                point_column: Some(widest_point),
+                ..PostingLayout::default()
+            },
+        })
+    }
+
+    /// The start of a posting line, counted from the end of the indentation: the flag, the
+    /// account and the separator before the units, and the number of the units, if any.
+    fn posting_head(
+        &self,
+        posting: &Posting<'_>,
+    ) -> Result<(String, Option<String>), BasicRendererError> {
+        let mut line = Vec::new();
+        let mut flag = Vec::new();
+        let posting_flag = posting.flag.as_ref().filter(|_| self.keep_posting_flags);
+        if let Some(posting_flag) = posting_flag {
+            if let Flag::Other(other) = posting_flag {
+                let rejected = self.posting_flag_policy == FlagPolicy::RejectOther;
+                if self.strict && (rejected || !is_valid_flag(other)) {
+                    return Err(BasicRendererError::InvalidPostingFlag(other.to_string()));
+                }
+            }
+            self.render(posting_flag, &mut flag)?;
+            write!(flag, " ")?;
+        }
+        let flag = String::from_utf8(flag)?;
+        let padding = self
+            .account_column
+            .map_or(0, |column| column.saturating_sub(flag.chars().count()));
+        write!(line, "{}{:padding$}", flag, "", padding = padding)?;
+        self.render(&posting.account, &mut line)?;
+        // Units may be a bare currency or missing entirely; only separate them from the account
+        // when there is something to write.
+        // A tab would be counted as a single column when aligning, so it is written as two
+        // spaces instead.
+        if posting.units.num.is_some() || posting.units.currency.is_some() {
+            match self.align_currency {
+                Some(_) => write!(line, "{}", self.posting_amount_prefix.replace('\t', "  "))?,
+                None => write!(line, "{}", self.posting_amount_prefix)?,
+            }
+        }
+        let number = match posting.units.num {
+            Some(mut num) => {
+                if self.display_mode && self.sign_convention.contains(&posting.account.ty) {
+                    num = -num;
+                }
+                let mut number = Vec::new();
+                self.write_number(&mut number, &num, posting.units.currency)?;
+                Some(String::from_utf8(number)?)
+            }
+            None => None,
+        };
+        Ok((String::from_utf8(line)?, number))
+    }
+
+    fn render_posting<W: Write>(
+        &self,
+        posting: &Posting<'_>,
+        layout: &PostingLayout,
+        w: &mut W,
+    ) -> Result<(), BasicRendererError> {
+        self.write_indent(w, 1)?;
+        // The part of the posting before the price is collected first so the price can be
+        // aligned against it.
+        let (mut head, number) = self.posting_head(posting)?;
+        if let Some(number) = number {
+            if let Some(point_column) = layout.point_column {
+                let width = head.chars().count() + number_widths(&number).0;
+                head.push_str(&" ".repeat(point_column.saturating_sub(width)));
+            }
+            head.push_str(&number);
+        }
+        let mut line = Vec::new();
+        write!(line, "{}", head)?;
+        match (posting.units.num, posting.units.currency) {
+            (Some(_), Some(_)) if layout.elide_currency => {}
+            (Some(_), Some(currency)) => match layout.currency_column {
+                Some(column) => {
+                    let padding = column.saturating_sub(head.chars().count()).max(1);
+                    let currency = self.currency_display(currency);
+                    write!(line, "{:padding$}{}", "", currency, padding = padding)?;
+                }
+                None => self.write_unit_currency(&mut line, currency)?,
+            },
+            (None, Some(currency)) => write!(line, "{}", self.currency_display(currency))?,
+            _ => {}
+        }
+        // Beancount expects the cost directly after the units and the price after the cost.
+        if let Some(cost) = &posting.cost {
+            write!(line, " ")?;
+            self.render(cost, &mut line)?;
+        }
+        let line = String::from_utf8(line)?;
+        write!(w, "{}", line)?;
+        if let Some(price) = &posting.price {
+            let padding = self.price_column.map_or(1, |column| {
+                column.saturating_sub(line.chars().count()).max(1)
+            });
+            let operator = if self.total_prices { "@@" } else { "@" };
+            write!(w, "{:padding$}{} ", "", operator, padding = padding)?;
+            self.render(price, w)?;
+        }
+        self.newline(w)?;
+        self.render_key_value(w, &posting.meta, 2)
+    }
+}
+
+impl<'a, W: Write> Renderer<&'a Flag, W> for BasicRenderer {
+    type Error = BasicRendererError;
+    fn render(&self, flag: &'a Flag, w: &mut W) -> Result<(), Self::Error> {
+        match flag {
+            Flag::Okay => write!(w, "*")?,
+            Flag::Warning => write!(w, "!")?,
+            // Beancount reads these characters as flags directly. Other letters are only read as a
+            // flag in the quoted form, as in `'A`. Anything else is written as is, which beancount
+            // cannot read back; strict mode rejects such flags instead.
+            Flag::Other(s) => match s.as_str() {
+                "&" | "#" | "?" | "%" | "P" | "S" | "T" | "C" | "U" | "R" | "M" => {
+                    write!(w, "{}", s)?
+                }
+                _ if s.len() == 1 && s.chars().all(|c| c.is_ascii_uppercase()) => {
+                    write!(w, "'{}", s)?
+                }
+                _ => write!(w, "{}", s)?,
+            },
+        };
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> Renderer<&'a CostSpec<'_>, W> for BasicRenderer {
+    type Error = BasicRendererError;
+    fn render(&self, cost: &'a CostSpec<'_>, w: &mut W) -> Result<(), Self::Error> {
+        // A per-unit number is rendered as `{10 USD}` and a total number as `{{20 USD}}`. When
+        // both are present, they are combined as `{10 # 20 USD}`.
+        let double_brackets = cost.number_per.is_none() && cost.number_total.is_some();
+        if double_brackets {
+            write!(w, "{{{{")?;
+        } else {
+            write!(w, "{{")?;
+        }
+        let mut first = true;
+
+        if cost.merge_cost {
+            write!(w, "*")?;
+            first = false;
+        }
+
+        let has_number = cost.number_per.is_some() || cost.number_total.is_some();
+        if !first && (has_number || cost.currency.is_some()) {
+            write!(w, ", ")?;
+        }
+        if let Some(number_per) = &cost.number_per {
+            self.write_number(w, number_per, cost.currency)?;
+        }
+        if let Some(number_total) = &cost.number_total {
+            if cost.number_per.is_some() {
+                write!(w, " # ")?;
+            }
+            self.write_number(w, number_total, cost.currency)?;
+        }
+        match cost.currency {
+            Some(currency) if has_number => self.write_unit_currency(w, currency)?,
+            Some(currency) => write!(w, "{}", self.currency_display(currency))?,
+            None => {}
+        }
+        if has_number || cost.currency.is_some() {
+            first = false;
+        }
+
+        if let Some(date) = &cost.date {
+            if !first {
+                write!(w, ", ")?;
+            }
+            self.write_date(w, date)?;
+            first = false;
+        }
+
+        if let Some(label) = &cost.label {
+            if !first {
+                write!(w, ", ")?;
+            }
+            self.write_quoted_str(w, label)?;
+        }
+
+        if double_brackets {
+            write!(w, "}}}}")?;
+        } else {
+            write!(w, "}}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> Renderer<&'a IncompleteAmount<'_>, W> for BasicRenderer {
+    type Error = BasicRendererError;
+    fn render(&self, incomplete_amount: &'a IncompleteAmount<'_>, w: &mut W) -> Result<(), Self::Error> {
+        match (&incomplete_amount.num, &incomplete_amount.currency) {
+            (Some(num), Some(currency)) => {
+                self.write_number(w, num, Some(*currency))?;
+                self.write_unit_currency(w, currency)?;
+            }
+            (None, Some(currency)) => write!(w, "{}", self.currency_display(currency))?,
+            (Some(num), None) => self.write_number(w, num, None)?,
+            _ => {}
+        };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Ledger<'_> {
+        beancount::parse(source).expect("valid ledger")
+    }
+
+    fn render(renderer: &BasicRenderer, source: &str) -> String {
+        renderer.render_to_string(&parse(source)).expect("renders")
+    }
+
+    /// Render `source` and assert that parsing and rendering the output gives it back unchanged.
+    fn round_trip(renderer: &BasicRenderer, source: &str) -> String {
+        let rendered = render(renderer, source);
+        assert_eq!(render(renderer, &rendered), rendered);
+        rendered
+    }
+
+    #[test]
+    fn keyword_column_aligns_accounts() {
+        let renderer = BasicRenderer {
+            keyword_column: Some(20),
+            ..BasicRenderer::default()
+        };
+        let rendered = round_trip(
+            &renderer,
+            "2019-01-01 open Assets:Cash\n2019-12-31 close Assets:Cash\n",
+        );
+        let columns: Vec<_> = rendered
+            .lines()
+            .filter_map(|line| line.find("Assets:Cash"))
+            .collect();
+        assert_eq!(columns, [20, 20]);
+    }
+
+    #[test]
+    fn append_summary_counts_directives() {
+        let renderer = BasicRenderer {
+            append_summary: true,
+            ..BasicRenderer::default()
+        };
+        let rendered = render(
+            &renderer,
+            "2019-01-01 open Assets:Cash\n\
+             2019-01-02 * \"Coffee\"\n  Assets:Cash -3 USD\n  Expenses:Food\n\
+             2019-01-03 * \"Tea\"\n  Assets:Cash -2 USD\n  Expenses:Food\n",
+        );
+        assert_eq!(
+            rendered.lines().last(),
+            Some("; 3 directives, 2 transactions")
+        );
+    }
+
+    #[test]
+    fn currency_only_posting() {
+        let rendered = round_trip(
+            &BasicRenderer::default(),
+            "2019-01-02 * \"Exchange\"\n  Assets:Cash -10 EUR\n  Assets:Bank USD\n",
+        );
+        assert!(rendered.contains("\n\tAssets:Bank\tUSD\n"));
+    }
+
+    #[test]
+    fn currency_precision_per_currency() {
+        let renderer = BasicRenderer {
+            currency_precision: HashMap::from([("JPY".to_string(), 0), ("BTC".to_string(), 8)]),
+            ..BasicRenderer::default()
+        };
+        let rendered = render(
+            &renderer,
+            "2019-01-02 * \"Exchange\"\n  Assets:Yen 1234.5 JPY\n  Assets:Coins 0.1 BTC\n",
+        );
+        assert!(rendered.contains("\tAssets:Yen\t1235 JPY\n"));
+        assert!(rendered.contains("\tAssets:Coins\t0.10000000 BTC\n"));
+    }
+
+    #[test]
+    fn account_rewriter_masks_accounts() {
+        fn mask<'a, 'b>(account: &'b Account<'a>) -> Cow<'b, Account<'a>> {
+            let mut account = account.clone();
+            if let Some(last) = account.parts.last_mut() {
+                *last = "XXXX";
+            }
+            Cow::Owned(account)
+        }
+        let renderer = BasicRenderer {
+            account_rewriter: Some(mask),
+            ..BasicRenderer::default()
+        };
+        let rendered = render(
+            &renderer,
+            "2019-01-01 open Assets:Bank:Acct1\n\
+             2019-01-02 * \"Coffee\"\n  Assets:Bank:Acct1 -3 USD\n  Expenses:Food\n",
+        );
+        assert!(!rendered.contains("Acct1"));
+        assert!(rendered.contains("open Assets:Bank:XXXX\n"));
+        assert!(rendered.contains("\tAssets:Bank:XXXX\t-3 USD\n"));
+        assert!(rendered.contains("\tExpenses:XXXX\n"));
+        assert_eq!(renderer.clone(), renderer);
+        assert_ne!(renderer, BasicRenderer::default());
+    }
+
+    #[test]
+    fn split_by_root() {
+        let ledger = parse("2019-01-01 open Assets:Cash\n2019-01-01 open Expenses:Food\n");
+        let buffers = BasicRenderer::default()
+            .render_split_by_root(&ledger)
+            .expect("renders");
+        assert_eq!(buffers.len(), 2);
+        assert!(buffers[&AccountType::Assets].contains("open Assets:Cash"));
+        assert!(!buffers[&AccountType::Assets].contains("Expenses"));
+        assert!(buffers[&AccountType::Expenses].contains("open Expenses:Food"));
+        assert!(!buffers[&AccountType::Expenses].contains("Assets"));
+    }
+
+    #[test]
+    fn tab_in_narration() {
+        let source = "2019-01-02 * \"Coffee\\tto go\"\n  Assets:Cash -3 USD\n  Expenses:Food\n";
+        let rendered = round_trip(&BasicRenderer::default(), source);
+        assert!(rendered.starts_with("2019-01-02 * \"Coffee\\tto go\"\n"));
+        let renderer = BasicRenderer {
+            tab_escape: TabEscape::Spaces,
+            ..BasicRenderer::default()
+        };
+        let rendered = render(&renderer, source);
+        assert!(rendered.starts_with("2019-01-02 * \"Coffee to go\"\n"));
+    }
+
+    #[test]
+    fn balance_amount_with_and_without_tolerance() {
+        let renderer = BasicRenderer::default();
+        assert_eq!(
+            render(&renderer, "2019-01-01 balance Assets:Cash 100.00 ~ 0.01 USD\n"),
+            "2019-01-01 balance Assets:Cash\t100.00 ~ 0.01 USD\n"
+        );
+        assert_eq!(
+            render(&renderer, "2019-01-01 balance Assets:Cash 100.00 USD\n"),
+            "2019-01-01 balance Assets:Cash\t100.00 USD\n"
+        );
+    }
+
+    #[test]
+    fn explicit_booking_none() {
+        let source = "2019-01-01 open Assets:Cash USD\n";
+        assert_eq!(
+            round_trip(&BasicRenderer::default(), source),
+            "2019-01-01 open Assets:Cash USD\n"
+        );
+        let renderer = BasicRenderer {
+            explicit_booking_none: true,
+            ..BasicRenderer::default()
+        };
+        assert_eq!(
+            round_trip(&renderer, source),
+            "2019-01-01 open Assets:Cash USD \"NONE\"\n"
+        );
+        for booking in ["STRICT", "AVERAGE", "FIFO", "LIFO"] {
+            let source = format!("2019-01-01 open Assets:Cash USD \"{}\"\n", booking);
+            assert_eq!(round_trip(&BasicRenderer::default(), &source), source);
+        }
+    }
+
+    #[test]
+    fn date_format_applies_to_every_dated_directive() {
+        let renderer = BasicRenderer {
+            date_format: DateFormat::Slashes,
+            ..BasicRenderer::default()
+        };
+        let rendered = round_trip(
+            &renderer,
+            "2019-01-01 open Assets:Cash\n\
+             2019-01-01 open Equity:Opening\n\
+             2019-01-01 commodity USD\n\
+             2019-01-02 pad Assets:Cash Equity:Opening\n\
+             2019-01-03 balance Assets:Cash 10 USD\n\
+             2019-01-04 * \"Coffee\"\n  Assets:Cash -3 USD\n  Expenses:Food\n\
+             2019-01-05 note Assets:Cash \"Counted\"\n\
+             2019-01-05 document Assets:Cash \"receipt.pdf\"\n\
+             2019-01-06 event \"location\" \"Home\"\n\
+             2019-01-06 query \"cash\" \"SELECT account\"\n\
+             2019-01-07 price USD 0.9 EUR\n\
+             2019-01-08 custom \"budget\" Expenses:Food 100 USD\n\
+             2019-01-09 close Assets:Cash\n",
+        );
+        let headers: Vec<_> = rendered
+            .lines()
+            .filter(|line| !line.is_empty() && !line.starts_with('\t'))
+            .collect();
+        assert_eq!(headers.len(), 13);
+        for header in headers {
+            assert!(header.starts_with("2019/01/0"), "{}", header);
+        }
+    }
+
+    #[test]
+    fn synthesize_opens_for_unopened_accounts() {
+        let renderer = BasicRenderer {
+            synthesize_opens: true,
+            ..BasicRenderer::default()
+        };
+        let rendered = round_trip(
+            &renderer,
+            "2019-01-01 open Assets:Cash\n\
+             2019-01-03 * \"Tea\"\n  Assets:Cash -2 USD\n  Expenses:Food\n\
+             2019-01-02 * \"Coffee\"\n  Assets:Cash -3 USD\n  Expenses:Food\n",
+        );
+        assert!(rendered.starts_with("2019-01-02 open Expenses:Food\n\n2019-01-01 open Assets:Cash\n"));
+        assert_eq!(rendered.matches(" open ").count(), 2);
+    }
+
+    #[test]
+    fn account_column_with_mixed_flags() {
+        let renderer = BasicRenderer {
+            account_column: Some(2),
+            ..BasicRenderer::default()
+        };
+        let rendered = round_trip(
+            &renderer,
+            "2019-01-02 * \"Coffee\"\n  ! Assets:Cash -3 USD\n  Expenses:Food 3 USD\n",
+        );
+        assert!(rendered.contains("\n\t! Assets:Cash\t-3 USD\n"));
+        assert!(rendered.contains("\n\t  Expenses:Food\t3 USD\n"));
+    }
+
+    #[test]
+    fn posting_sort_preserve_is_the_default() {
+        assert_eq!(BasicRenderer::default().posting_sort, PostingSort::Preserve);
+        let source = "2019-01-02 * \"Shopping\"\n\
+                      \tExpenses:Food\n\
+                      \tAssets:Cash\t-3 USD\n\
+                      \tExpenses:Books\t1 USD\n";
+        assert_eq!(round_trip(&BasicRenderer::default(), source), source);
+    }
+
+    #[test]
+    fn strict_commodity_symbol() {
+        let renderer = BasicRenderer {
+            strict: true,
+            ..BasicRenderer::default()
+        };
+        assert_eq!(
+            render(&renderer, "2019-01-01 commodity USD\n"),
+            "2019-01-01 commodity USD\n"
+        );
+        let result = renderer.render_to_string(&parse("2019-01-01 commodity usd\n"));
+        assert!(matches!(
+            result,
+            Err(BasicRendererError::InvalidCurrency(currency)) if currency == "usd"
+        ));
+    }
+
+    #[test]
+    fn max_postings_warning() {
+        let renderer = BasicRenderer {
+            max_postings_warn: Some(2),
+            ..BasicRenderer::default()
+        };
+        let rendered = render(
+            &renderer,
+            "2019-01-02 * \"Split\"\n  Assets:Cash -3 USD\n  Expenses:Food 1 USD\n  Expenses:Books\n\
+             2019-01-03 * \"Coffee\"\n  Assets:Cash -3 USD\n  Expenses:Food\n",
+        );
+        assert!(rendered.starts_with("; WARNING: 3 postings\n2019-01-02 * \"Split\"\n"));
+        assert_eq!(rendered.matches("; WARNING").count(), 1);
+    }
+
+    #[test]
+    fn multiline_query_round_trip() {
+        let query = "SELECT account, sum(position)\nWHERE account ~ \"Expenses\"\nGROUP BY account";
+        let ledger = parse(
+            "2019-01-01 query \"expenses\" \"SELECT account, sum(position)\\n\
+             WHERE account ~ \\\"Expenses\\\"\\nGROUP BY account\"\n",
+        );
+        let rendered = BasicRenderer::default().render_to_string(&ledger).expect("renders");
+        assert_eq!(rendered.lines().count(), 1);
+        match &parse(&rendered).directives[0] {
+            Directive::Query(parsed) => assert_eq!(parsed.query_string, query),
+            directive => panic!("expected a query, got {:?}", directive),
+        }
+    }
+
+    #[test]
+    fn empty_meta_value() {
+        let source = "2019-01-01 open Assets:Cash\n  note:\n";
+        assert_eq!(
+            round_trip(&BasicRenderer::default(), source),
+            "2019-01-01 open Assets:Cash\n\tnote:\n"
+        );
+        let renderer = BasicRenderer {
+            empty_meta_value: EmptyMetaValue::EmptyQuoted,
+            ..BasicRenderer::default()
+        };
+        assert_eq!(
+            round_trip(&renderer, source),
+            "2019-01-01 open Assets:Cash\n\tnote: \"\"\n"
+        );
+    }
+
+    #[test]
+    fn render_transformed_drops_and_rewrites() {
+        let ledger = parse(
+            "2019-01-01 price EUR 1.1 USD\n\
+             2019-01-02 * \"Coffee\"\n  Assets:Cash -3 USD\n  Expenses:Food\n",
+        );
+        let mut rendered = Vec::new();
+        BasicRenderer::default()
+            .render_transformed(
+                &ledger,
+                |directive| match directive {
+                    Directive::Price(_) => None,
+                    Directive::Transaction(mut transaction) => {
+                        let narration = transaction.narration.to_uppercase();
+                        transaction.narration = Box::leak(narration.into_boxed_str());
+                        Some(Directive::Transaction(transaction))
+                    }
+                    directive => Some(directive),
+                },
+                &mut rendered,
+            )
+            .expect("renders");
+        let rendered = String::from_utf8(rendered).expect("utf-8");
+        assert!(!rendered.contains("price"));
+        assert!(rendered.starts_with("2019-01-02 * \"COFFEE\"\n"));
+    }
+
+    #[test]
+    fn currency_symbols_in_display_mode() {
+        let source = "2019-01-02 * \"Coffee\"\n  Assets:Cash -3 EUR\n  Expenses:Food 3 EUR\n";
+        let mut renderer = BasicRenderer {
+            currency_symbols: HashMap::from([("EUR".to_string(), "€".to_string())]),
+            ..BasicRenderer::default()
+        };
+        assert!(render(&renderer, source).contains("\tExpenses:Food\t3 EUR\n"));
+        renderer.display_mode = true;
+        let rendered = render(&renderer, source);
+        assert!(rendered.contains("\tAssets:Cash\t-3€\n"));
+        assert!(rendered.contains("\tExpenses:Food\t3€\n"));
+    }
+
+    #[test]
+    fn sorted_accounts() {
+        let ledger = parse(
+            "2019-01-01 open Expenses:Food\n\
+             2019-01-02 open Assets:Cash\n\
+             2019-01-02 * \"Coffee\"\n  Assets:Cash -3 USD\n  Expenses:Food\n\
+             2019-01-03 open Assets:Bank\n",
+        );
+        let mut rendered = Vec::new();
+        BasicRenderer::default()
+            .render_sorted_accounts(&ledger, &mut rendered)
+            .expect("renders");
+        let rendered = String::from_utf8(rendered).expect("utf-8");
+        let lines: Vec<_> = rendered.lines().filter(|line| !line.is_empty()).collect();
+        assert_eq!(
+            lines,
+            [
+                "2019-01-03 open Assets:Bank",
+                "2019-01-02 open Assets:Cash",
+                "2019-01-01 open Expenses:Food",
+            ]
+        );
+    }
+
+    #[test]
+    fn long_narration_warning() {
+        let renderer = BasicRenderer {
+            narration_warn_len: Some(6),
+            ..BasicRenderer::default()
+        };
+        let rendered = render(
+            &renderer,
+            "2019-01-02 * \"Coffee\"\n  Assets:Cash -3 USD\n  Expenses:Food\n\
+             2019-01-03 * \"Coffee beans\"\n  Assets:Cash -9 USD\n  Expenses:Food\n",
+        );
+        assert_eq!(rendered.matches("; WARNING: long narration\n").count(), 1);
+        assert!(rendered.contains("; WARNING: long narration\n2019-01-03 * \"Coffee beans\"\n"));
+    }
+
+    #[test]
+    fn flag_policies() {
+        let ledger = parse(
+            "2019-01-02 'A \"Coffee\"\n  Assets:Cash -3 USD\n  Expenses:Food\n\
+             2019-01-03 * \"Tea\"\n  'B Assets:Cash -2 USD\n  Expenses:Food\n",
+        );
+        let transaction = Ledger {
+            directives: ledger.directives[..1].to_vec(),
+        };
+        let posting = Ledger {
+            directives: ledger.directives[1..].to_vec(),
+        };
+        let mut renderer = BasicRenderer {
+            strict: true,
+            ..BasicRenderer::default()
+        };
+        assert!(renderer.render_to_string(&ledger).is_ok());
+        renderer.transaction_flag_policy = FlagPolicy::RejectOther;
+        assert!(matches!(
+            renderer.render_to_string(&transaction),
+            Err(BasicRendererError::InvalidTransactionFlag(flag)) if flag == "A"
+        ));
+        assert!(renderer.render_to_string(&posting).is_ok());
+        renderer.transaction_flag_policy = FlagPolicy::AllowOther;
+        renderer.posting_flag_policy = FlagPolicy::RejectOther;
+        assert!(renderer.render_to_string(&transaction).is_ok());
+        assert!(matches!(
+            renderer.render_to_string(&posting),
+            Err(BasicRendererError::InvalidPostingFlag(flag)) if flag == "B"
+        ));
+    }
+
+    #[test]
+    fn price_column_aligns_prices() {
+        let renderer = BasicRenderer {
+            price_column: Some(40),
+            ..BasicRenderer::default()
+        };
+        let rendered = round_trip(
+            &renderer,
+            "2019-01-02 * \"Exchange\"\n\
+             \tAssets:Cash\t-100 EUR @ 1.1 USD\n\
+             \tAssets:Broker:Savings\t-1000.50 CHF @ 1.05 USD\n\
+             \tAssets:Bank\n",
+        );
+        let columns: Vec<_> = rendered
+            .lines()
+            .filter_map(|line| line.find(" @ "))
+            .collect();
+        assert_eq!(columns, [40, 40]);
+    }
+
+    #[test]
+    fn uppercase_currencies() {
+        let mut ledger = parse(
+            "2019-01-01 open Assets:Cash USD\n\
+             2019-01-02 * \"Coffee\"\n  Assets:Cash -3 USD\n  Expenses:Food\n",
+        );
+        if let Directive::Open(open) = &mut ledger.directives[0] {
+            open.currencies = vec!["usd"];
+        }
+        if let Directive::Transaction(transaction) = &mut ledger.directives[1] {
+            transaction.postings[0].units.currency = Some("Usd");
+        }
+        let renderer = BasicRenderer {
+            uppercase_currencies: true,
+            ..BasicRenderer::default()
+        };
+        let rendered = renderer.render_to_string(&ledger).expect("renders");
+        assert!(rendered.contains("open Assets:Cash USD\n"));
+        assert!(rendered.contains("\tAssets:Cash\t-3 USD\n"));
+        assert_eq!(render(&renderer, &rendered), rendered);
+    }
+
+    #[test]
+    fn narration_placeholder() {
+        let source = "2019-01-02 * \"\"\n  Assets:Cash -3 USD\n  Expenses:Food\n\
+                      2019-01-03 * \"Tea\"\n  Assets:Cash -2 USD\n  Expenses:Food\n";
+        let rendered = render(&BasicRenderer::default(), source);
+        assert!(rendered.starts_with("2019-01-02 * \"\"\n"));
+        let renderer = BasicRenderer {
+            narration_placeholder: Some("TODO".to_string()),
+            ..BasicRenderer::default()
+        };
+        let rendered = render(&renderer, source);
+        assert!(rendered.starts_with("2019-01-02 * \"TODO\"\n"));
+        assert!(rendered.contains("2019-01-03 * \"Tea\"\n"));
+        assert_eq!(rendered.matches("TODO").count(), 1);
+    }
+
+    #[test]
+    fn pad_balance_block() {
+        let ledger = parse(
+            "2019-01-01 pad Assets:Cash Equity:Opening\n\
+             2019-01-02 balance Assets:Cash 100 USD\n",
+        );
+        let (pad, balance) = match &ledger.directives[..] {
+            [Directive::Pad(pad), Directive::Balance(balance)] => (pad, balance),
+            directives => panic!("unexpected directives {:?}", directives),
+        };
+        let mut rendered = Vec::new();
+        BasicRenderer::default()
+            .render_pad_balance(pad, balance, &mut rendered)
+            .expect("renders");
+        assert_eq!(
+            String::from_utf8(rendered).expect("utf-8"),
+            "2019-01-01 pad Assets:Cash Equity:Opening\n\
+             2019-01-02 balance Assets:Cash\t100 USD\n"
+        );
+    }
+
+    #[test]
+    fn render_meta_disabled() {
+        let source = "2019-01-01 open Assets:Cash\n\tnote: \"Wallet\"\n\
+                      2019-01-02 * \"Coffee\"\n\timport-id: 12\n\treceipt: \"r.pdf\"\n\
+                      \tAssets:Cash\t-3 USD\n\t\tcategory: \"food\"\n\tExpenses:Food\n";
+        let renderer = BasicRenderer {
+            render_meta: false,
+            meta_comment_keys: HashSet::from(["import-id".to_string()]),
+            checksums: true,
+            ..BasicRenderer::default()
+        };
+        assert_eq!(
+            render(&renderer, source),
+            "2019-01-01 open Assets:Cash\n\
+             \n\
+             2019-01-02 * \"Coffee\"\n\
+             \tAssets:Cash\t-3 USD\n\
+             \tExpenses:Food\n"
+        );
+    }
+
+    #[test]
+    fn debug_indices() {
+        let renderer = BasicRenderer {
+            debug_indices: true,
+            ..BasicRenderer::default()
+        };
+        let rendered = render(
+            &renderer,
+            "option \"title\" \"Test\"\n\
+             2019-01-01 open Assets:Cash\n\
+             2019-01-02 close Assets:Cash\n",
+        );
+        let indices: Vec<_> = rendered
+            .lines()
+            .filter(|line| line.starts_with("; [#"))
+            .collect();
+        assert_eq!(indices, ["; [#0]", "; [#1]", "; [#2]"]);
+        assert!(rendered.contains("; [#1]\n2019-01-01 open Assets:Cash\n"));
+        assert!(rendered.contains("; [#2]\n2019-01-02 close Assets:Cash\n"));
+    }
+
+    #[test]
+    fn sign_convention_flips_income() {
+        let source = "2019-01-31 * \"Salary\"\n  Income:Salary -1000 USD\n  Assets:Bank 1000 USD\n";
+        let mut renderer = BasicRenderer {
+            sign_convention: HashSet::from([AccountType::Income]),
+            ..BasicRenderer::default()
+        };
+        assert!(render(&renderer, source).contains("\tIncome:Salary\t-1000 USD\n"));
+        renderer.display_mode = true;
+        let rendered = render(&renderer, source);
+        assert!(rendered.contains("\tIncome:Salary\t1000 USD\n"));
+        assert!(rendered.contains("\tAssets:Bank\t1000 USD\n"));
+    }
+
+    #[test]
+    fn warn_date_order() {
+        let renderer = BasicRenderer {
+            warn_date_order: true,
+            ..BasicRenderer::default()
+        };
+        let rendered = render(
+            &renderer,
+            "2019-01-03 * \"Tea\"\n  Assets:Cash -2 USD\n  Expenses:Food\n\
+             2019-01-02 * \"Coffee\"\n  Assets:Cash -3 USD\n  Expenses:Food\n",
+        );
+        assert!(rendered.starts_with("2019-01-03 * \"Tea\"\n"));
+        assert!(rendered.contains("\n; WARNING: date out of order\n2019-01-02 * \"Coffee\"\n"));
+        assert_eq!(rendered.matches("; WARNING").count(), 1);
+    }
+
+    #[test]
+    fn warn_unused_currencies() {
+        let renderer = BasicRenderer {
+            warn_unused_currencies: true,
+            ..BasicRenderer::default()
+        };
+        let rendered = render(
+            &renderer,
+            "2019-01-01 open Assets:Cash USD,EUR\n\
+             2019-01-02 * \"Coffee\"\n  Assets:Cash -3 USD\n  Expenses:Food\n",
+        );
+        assert!(rendered.starts_with(
+            "; WARNING: currency EUR of Assets:Cash is never used\n\
+             2019-01-01 open Assets:Cash USD,EUR\n"
+        ));
+        assert_eq!(rendered.matches("; WARNING").count(), 1);
+    }
+
+    #[test]
+    fn document_base_makes_paths_relative() {
+        let source = "2019-01-02 document Assets:Cash \"/home/me/ledger/docs/receipt.pdf\"\n\
+                      2019-01-03 document Assets:Cash \"/tmp/other.pdf\"\n";
+        let renderer = BasicRenderer {
+            document_base: Some(PathBuf::from("/home/me/ledger")),
+            ..BasicRenderer::default()
+        };
+        let rendered = round_trip(&renderer, source);
+        assert!(rendered.contains("document Assets:Cash \"docs/receipt.pdf\"\n"));
+        assert!(rendered.contains("document Assets:Cash \"/tmp/other.pdf\"\n"));
+    }
+
+    #[test]
+    fn render_to_string_sized_stats() {
+        let ledger = parse(
+            "2019-01-01 open Assets:Cash\n\
+             2019-01-02 * \"Coffee\"\n  Assets:Cash -3 USD\n  Expenses:Food\n\
+             2019-01-03 * \"Split\"\n  Assets:Cash -3 USD\n  Expenses:Food 1 USD\n  Expenses:Books\n",
+        );
+        let renderer = BasicRenderer::default();
+        let (rendered, stats) = renderer.render_to_string_sized(&ledger).expect("renders");
+        assert_eq!(rendered, renderer.render_to_string(&ledger).expect("renders"));
+        assert_eq!(
+            stats,
+            RenderStats {
+                directives: 3,
+                transactions: 2,
+                postings: 5,
+                bytes: rendered.len(),
+                lines: rendered.lines().count(),
+            }
+        );
+    }
+
+    #[test]
+    fn two_space_posting_amount_prefix() {
+        let renderer = BasicRenderer {
+            posting_amount_prefix: Cow::Borrowed("  "),
+            ..BasicRenderer::default()
+        };
+        let rendered = round_trip(
+            &renderer,
+            "2019-01-02 * \"Coffee\"\n  Assets:Cash -3 USD\n  Expenses:Food USD\n  Expenses:Tips\n",
+        );
+        assert!(rendered.contains("\n\tAssets:Cash  -3 USD\n"));
+        assert!(rendered.contains("\n\tExpenses:Food  USD\n"));
+        assert!(rendered.contains("\n\tExpenses:Tips\n"));
+    }
+
+    #[test]
+    fn currency_rename_everywhere() {
+        let renderer = BasicRenderer {
+            currency_rename: HashMap::from([("TOKEN".to_string(), "COIN".to_string())]),
+            ..BasicRenderer::default()
+        };
+        let rendered = round_trip(
+            &renderer,
+            "2019-01-01 commodity TOKEN\n\
+             2019-01-01 open Assets:Wallet TOKEN\n\
+             2019-01-02 price TOKEN 2 USD\n\
+             2019-01-03 * \"Buy\"\n  Assets:Wallet 10 TOKEN {2 USD}\n  Assets:Cash\n\
+             2019-01-04 * \"Sell\"\n  Assets:Cash 30 USD @ 3 TOKEN\n  Assets:Wallet\n\
+             2019-01-05 balance Assets:Wallet 0 TOKEN\n",
+        );
+        assert!(!rendered.contains("TOKEN"));
+        assert_eq!(rendered.matches("COIN").count(), 6);
+    }
+
+    #[test]
+    fn net_comment() {
+        let renderer = BasicRenderer {
+            net_comment: true,
+            ..BasicRenderer::default()
+        };
+        let rendered = render(
+            &renderer,
+            "2019-01-02 * \"Coffee\"\n  Assets:Cash -3.00 USD\n  Expenses:Food 3.00 USD\n\
+             2019-01-03 * \"Tea\"\n  Assets:Cash -2.00 USD\n  Expenses:Food 2.01 USD\n  Assets:Cash 1 EUR\n",
+        );
+        let comments: Vec<_> = rendered
+            .lines()
+            .filter(|line| line.starts_with("; net:"))
+            .collect();
+        assert_eq!(comments, ["; net: 0.00 USD", "; net: 1 EUR, 0.01 USD"]);
+    }
+
+    #[test]
+    fn render_flattened_include_chain() {
+        let dir = std::env::temp_dir().join(format!("beancount_render_{}", std::process::id()));
+        fs::create_dir_all(dir.join("accounts")).expect("creates directory");
+        fs::write(
+            dir.join("accounts/cash.beancount"),
+            "include \"opening.beancount\"\n2019-01-02 close Assets:Cash\n",
+        )
+        .expect("writes file");
+        fs::write(
+            dir.join("accounts/opening.beancount"),
+            "2019-01-01 open Assets:Cash\n",
+        )
+        .expect("writes file");
+        fs::write(dir.join("cycle.beancount"), "include \"cycle.beancount\"\n").expect("writes file");
+        let source = "option \"title\" \"Test\"\ninclude \"accounts/cash.beancount\"\n";
+        fs::write(dir.join("main.beancount"), source).expect("writes file");
+        let source_with_cycle = "include \"cycle.beancount\"\n";
+        fs::write(dir.join("with_cycle.beancount"), source_with_cycle).expect("writes file");
+
+        let renderer = BasicRenderer::default();
+        let ledger = parse(source);
+        let mut rendered = Vec::new();
+        renderer
+            .render_flattened(&ledger, &dir.join("main.beancount"), &mut rendered)
+            .expect("renders");
+        let rendered = String::from_utf8(rendered).expect("utf-8");
+        let lines: Vec<_> = rendered.lines().filter(|line| !line.is_empty()).collect();
+        assert_eq!(
+            lines,
+            [
+                "option \"title\" \"Test\"",
+                "2019-01-01 open Assets:Cash",
+                "2019-01-02 close Assets:Cash",
+            ]
+        );
+
+        let ledger = parse(source_with_cycle);
+        let path = dir.join("with_cycle.beancount");
+        let result = renderer.render_flattened(&ledger, &path, &mut Vec::new());
+        fs::remove_dir_all(&dir).expect("removes directory");
+        assert!(matches!(result, Err(BasicRendererError::IncludeCycle(_))));
+    }
+
+    #[test]
+    fn render_flattened_self_include() {
+        let dir = std::env::temp_dir().join(format!("beancount_render_self_{}", std::process::id()));
+        fs::create_dir_all(&dir).expect("creates directory");
+        let source = "2019-01-01 open Assets:Cash\ninclude \"main.beancount\"\n";
+        let path = dir.join("main.beancount");
+        fs::write(&path, source).expect("writes file");
+        let mut rendered = Vec::new();
+        let result = BasicRenderer::default().render_flattened(&parse(source), &path, &mut rendered);
+        fs::remove_dir_all(&dir).expect("removes directory");
+        assert!(matches!(result, Err(BasicRendererError::IncludeCycle(_))));
+        let rendered = String::from_utf8(rendered).expect("utf-8");
+        assert_eq!(rendered.matches("open Assets:Cash").count(), 1);
+    }
+
+    #[test]
+    fn strip_posting_flags() {
+        let source = "2019-01-02 ! \"Coffee\"\n  ! Assets:Cash -3 USD\n  Expenses:Food\n";
+        assert!(render(&BasicRenderer::default(), source).contains("\n\t! Assets:Cash\t-3 USD\n"));
+        let renderer = BasicRenderer {
+            keep_posting_flags: false,
+            ..BasicRenderer::default()
+        };
+        let rendered = round_trip(&renderer, source);
+        assert!(rendered.starts_with("2019-01-02 ! \"Coffee\"\n"));
+        assert!(rendered.contains("\n\tAssets:Cash\t-3 USD\n"));
+    }
+
+    #[cfg(feature = "diff")]
+    #[test]
+    fn render_diff() {
+        let renderer = BasicRenderer::default();
+        let ledger = parse("2019-01-01 open Assets:Cash\n2019-01-02 close Assets:Cash\n");
+        let previous = renderer.render_to_string(&ledger).expect("renders");
+        assert_eq!(renderer.render_diff(&previous, &ledger).expect("renders"), "");
+        let mutated = parse("2019-01-01 open Assets:Cash\n2019-01-03 close Assets:Cash\n");
+        let diff = renderer.render_diff(&previous, &mutated).expect("renders");
+        assert!(diff.starts_with("--- previous\n+++ rendered\n"));
+        assert!(diff.contains("\n-2019-01-02 close Assets:Cash\n+2019-01-03 close Assets:Cash\n"));
+    }
+
+    #[test]
+    fn dates_are_zero_padded() {
+        let rendered = render(
+            &BasicRenderer::default(),
+            "2019-1-2 open Assets:Cash\n2019-1-3 * \"Coffee\"\n  Assets:Cash -3 USD\n  Expenses:Food\n",
+        );
+        assert!(rendered.starts_with("2019-01-02 open Assets:Cash\n"));
+        assert!(rendered.contains("\n2019-01-03 * \"Coffee\"\n"));
+    }
+
+    #[test]
+    fn payee_banners() {
+        let renderer = BasicRenderer {
+            payee_banners: true,
+            ..BasicRenderer::default()
+        };
+        let rendered = render(
+            &renderer,
+            "2019-01-01 open Assets:Cash\n\
+             2019-01-02 * \"Cafe\" \"Coffee\"\n  Assets:Cash -3 USD\n  Expenses:Food\n\
+             2019-01-03 * \"Cafe\" \"Tea\"\n  Assets:Cash -2 USD\n  Expenses:Food\n",
+        );
+        assert_eq!(rendered.matches("; Payee: Cafe\n").count(), 1);
+        assert!(rendered.contains("\n; Payee: Cafe\n2019-01-02 * \"Cafe\" \"Coffee\"\n"));
+    }
+
+    #[test]
+    fn elide_currencies_in_display_mode() {
+        let source = "2019-01-02 * \"Split\"\n\
+                      \tAssets:Cash\t-3 USD\n\tExpenses:Food\t2 USD\n\tExpenses:Books\t1 USD\n";
+        let mut renderer = BasicRenderer {
+            elide_currencies: true,
+            ..BasicRenderer::default()
+        };
+        assert_eq!(render(&renderer, source).matches(" USD\n").count(), 3);
+        renderer.display_mode = true;
+        let rendered = render(&renderer, source);
+        assert!(rendered.contains("\n\tAssets:Cash\t-3 USD\n"));
+        assert!(rendered.contains("\n\tExpenses:Food\t2\n"));
+        assert!(rendered.contains("\n\tExpenses:Books\t1\n"));
+    }
+
+    #[test]
+    fn max_account_depth() {
+        let source = "2019-01-02 * \"Dinner\"\n\
+                      \tExpenses:Food:Restaurants:Thai\t30 USD\n\
+                      \tAssets:Cash\n";
+        let mut renderer = BasicRenderer {
+            display_mode: true,
+            max_account_depth: Some(2),
+            ..BasicRenderer::default()
+        };
+        let rendered = render(&renderer, source);
+        assert!(rendered.contains("\n\tExpenses:Food\t30 USD\n"));
+        assert!(rendered.contains("\n\tAssets:Cash\n"));
+        for depth in [0, 1] {
+            renderer.max_account_depth = Some(depth);
+            let rendered = render(&renderer, source);
+            assert!(rendered.contains("\n\tExpenses\t30 USD\n"));
+            assert!(rendered.contains("\n\tAssets\n"));
+        }
+    }
+
+    #[test]
+    fn checksums() {
+        let renderer = BasicRenderer {
+            checksums: true,
+            ..BasicRenderer::default()
+        };
+        let source = "option \"title\" \"Test\"\n\
+                      2019-01-01 open Assets:Cash\n\
+                      2019-01-02 * \"Coffee\"\n  Assets:Cash -3 USD\n  Expenses:Food\n";
+        let rendered = render(&renderer, source);
+        assert_eq!(render(&renderer, source), rendered);
+        let checksums: Vec<_> = rendered
+            .lines()
+            .filter_map(|line| line.strip_prefix("\tchecksum: \""))
+            .collect();
+        assert_eq!(checksums.len(), 2);
+        for checksum in &checksums {
+            let checksum = checksum.strip_suffix('"').expect("quoted");
+            assert_eq!(checksum.len(), 16);
+            assert!(checksum.chars().all(|c| c.is_ascii_hexdigit()));
+        }
+        assert_ne!(checksums[0], checksums[1]);
+        assert!(rendered.starts_with(
+            "option \"title\" \"Test\"\n\n2019-01-01 open Assets:Cash\n\tchecksum: \""
+        ));
+    }
+
+    #[test]
+    fn checksum_replaces_existing() {
+        let renderer = BasicRenderer {
+            checksums: true,
+            ..BasicRenderer::default()
+        };
+        let source = "2019-01-01 open Assets:Cash\n\tchecksum: \"0000000000000000\"\n";
+        let rendered = render(&renderer, source);
+        assert_eq!(rendered.matches("checksum:").count(), 1);
+        assert!(!rendered.contains("0000000000000000"));
+        assert_eq!(render(&renderer, &rendered), rendered);
+    }
+
+    #[test]
+    fn balance_period_break() {
+        let renderer = BasicRenderer {
+            balance_period_break: Some(Period::Month),
+            ..BasicRenderer::default()
+        };
+        assert_eq!(
+            render(
+                &renderer,
+                "2019-01-30 balance Assets:Cash 10 USD\n\
+                 2019-01-31 balance Assets:Cash 10 USD\n\
+                 2019-02-01 balance Assets:Cash 10 USD\n",
+            ),
+            "2019-01-30 balance Assets:Cash\t10 USD\n\
+             \n\
+             2019-01-31 balance Assets:Cash\t10 USD\n\
+             \n\
+             \n\
+             2019-02-01 balance Assets:Cash\t10 USD\n"
+        );
+    }
+
+    #[test]
+    fn quotes_and_backslashes_round_trip() {
+        let narration = "Coffee \"to go\" \\ cake";
+        let ledger = parse(
+            "2019-01-02 * \"Cafe \\\"Bar\\\"\" \"Coffee \\\"to go\\\" \\\\ cake\"\n\
+             \tAssets:Cash\t-3 USD\n\tExpenses:Food\n",
+        );
+        let rendered = BasicRenderer::default().render_to_string(&ledger).expect("renders");
+        assert!(rendered.starts_with(
+            "2019-01-02 * \"Cafe \\\"Bar\\\"\" \"Coffee \\\"to go\\\" \\\\ cake\"\n"
+        ));
+        match &parse(&rendered).directives[0] {
+            Directive::Transaction(transaction) => {
+                assert_eq!(transaction.payee, Some("Cafe \"Bar\""));
+                assert_eq!(transaction.narration, narration);
+            }
+            directive => panic!("expected a transaction, got {:?}", directive),
+        }
+    }
+
+    #[test]
+    fn empty_tags_and_links() {
+        let mut ledger = parse(
+            "2019-01-02 * \"Coffee\"\n  Assets:Cash -3 USD\n  Expenses:Food\n\
+             2019-01-02 note Assets:Cash \"Counted\"\n\
+             2019-01-02 document Assets:Cash \"receipt.pdf\"\n",
+        );
+        let renderer = BasicRenderer::default();
+        let rendered = renderer.render_to_string(&ledger).expect("renders");
+        assert!(rendered.lines().all(|line| !line.ends_with(char::is_whitespace)));
+        for directive in &mut ledger.directives {
+            match directive {
+                Directive::Transaction(transaction) => transaction.tags = vec![""],
+                Directive::Note(note) => note.links = vec![""],
+                Directive::Document(document) => document.tags = vec![""],
+                _ => {}
+            }
+        }
+        assert_eq!(renderer.render_to_string(&ledger).expect("renders"), rendered);
+    }
+
+    #[test]
+    fn metadata_order_is_deterministic() {
+        let source = "2019-01-01 open Assets:Cash\n\
+                      \tzeta: 1\n\tbeta: 2\n\talpha: 3\n\tgamma: 4\n\tdelta: 5\n";
+        let renderer = BasicRenderer::default();
+        let rendered = render(&renderer, source);
+        for _ in 0..10 {
+            assert_eq!(render(&renderer, source), rendered);
+        }
+        assert_eq!(
+            rendered,
+            "2019-01-01 open Assets:Cash\n\
+             \talpha: \"3\"\n\tbeta: \"2\"\n\tdelta: \"5\"\n\tgamma: \"4\"\n\tzeta: \"1\"\n"
+        );
+    }
+
+    #[test]
+    fn checksum_sorted_with_metadata() {
+        let renderer = BasicRenderer {
+            checksums: true,
+            ..BasicRenderer::default()
+        };
+        let source = "2019-01-01 open Assets:Cash\n\tbank: \"Example\"\n\tzone: \"EU\"\n";
+        let rendered = render(&renderer, source);
+        let keys: Vec<_> = rendered
+            .lines()
+            .skip(1)
+            .filter_map(|line| line.trim_start().split(':').next())
+            .collect();
+        assert_eq!(keys, ["bank", "checksum", "zone"]);
+    }
+
+    #[test]
+    fn tabs_in_values_are_not_indentation() {
+        let renderer = BasicRenderer {
+            indent: Indent::Spaces(2),
+            posting_amount_prefix: Cow::Borrowed("  "),
+            ..BasicRenderer::default()
+        };
+        let rendered = round_trip(
+            &renderer,
+            "2019-01-02 * \"Coffee\"\n\
+             \tnote: \"\\tto go\"\n\
+             \tAssets:Cash\t-3 USD\n\
+             \t\tnote: \"\\tcash\"\n\
+             \tExpenses:Food\n",
+        );
+        assert!(!rendered.contains('\t'));
+        assert_eq!(
+            rendered,
+            "2019-01-02 * \"Coffee\"\n\
+             \x20 note: \"\\tto go\"\n\
+             \x20 Assets:Cash  -3 USD\n\
+             \x20   note: \"\\tcash\"\n\
+             \x20 Expenses:Food\n"
+        );
+    }
+
+    #[test]
+    fn meta_order_modes() {
+        let source = "2019-01-01 open Assets:Cash\n\tdescription: \"Wallet\"\n\tbank: \"None\"\n";
+        let expected = "2019-01-01 open Assets:Cash\n\tbank: \"None\"\n\tdescription: \"Wallet\"\n";
+        for meta_order in [MetaOrder::Sorted, MetaOrder::AsParsed] {
+            let renderer = BasicRenderer {
+                meta_order,
+                ..BasicRenderer::default()
+            };
+            // `AsParsed` falls back to sorting, as the core does not keep the parsed order.
+            assert_eq!(render(&renderer, source), expected);
+        }
+    }
+
+    #[test]
+    fn posting_sort_elided_last() {
+        let renderer = BasicRenderer {
+            posting_sort: PostingSort::ElidedLast,
+            ..BasicRenderer::default()
+        };
+        assert_eq!(
+            round_trip(
+                &renderer,
+                "2019-01-02 * \"Shopping\"\n\
+                 \tAssets:Cash\t-3 USD\n\
+                 \tExpenses:Food\n\
+                 \tExpenses:Books\t1 USD\n\
+                 \tExpenses:Tips\t1 USD\n",
+            ),
+            "2019-01-02 * \"Shopping\"\n\
+             \tAssets:Cash\t-3 USD\n\
+             \tExpenses:Books\t1 USD\n\
+             \tExpenses:Tips\t1 USD\n\
+             \tExpenses:Food\n"
+        );
+    }
+
+    #[test]
+    fn payee_rename() {
+        let renderer = BasicRenderer {
+            payee_rename: HashMap::from([("AMZN MKTP".to_string(), "Amazon".to_string())]),
+            ..BasicRenderer::default()
+        };
+        let rendered = render(
+            &renderer,
+            "2019-01-02 * \"AMZN MKTP\" \"Books\"\n  Assets:Cash -3 USD\n  Expenses:Books\n",
+        );
+        assert!(rendered.starts_with("2019-01-02 * \"Amazon\" \"Books\"\n"));
+        assert!(!rendered.contains("AMZN"));
+    }
+
+    #[test]
+    fn currencies_are_never_quoted() {
+        let rendered = render(
+            &BasicRenderer::default(),
+            "2019-01-01 open Assets:Cash USD\n\
+             2019-01-02 * \"Coffee\"\n  Assets:Cash -3 USD\n  Expenses:Food\n",
+        );
+        assert!(!rendered.contains("\"USD\""));
+        assert!(rendered.contains("\tAssets:Cash\t-3 USD\n"));
+    }
+
+    #[test]
+    fn render_fmt() {
+        struct Failing;
+        impl fmt::Write for Failing {
+            fn write_str(&mut self, _: &str) -> fmt::Result {
+                Err(fmt::Error)
+            }
+        }
+        let ledger = parse("2019-01-02 * \"Coffee\"\n  Assets:Cash -3 USD\n  Expenses:Food\n");
+        let renderer = BasicRenderer::default();
+        let mut rendered = String::new();
+        renderer.render_fmt(&ledger, &mut rendered).expect("renders");
+        assert_eq!(rendered, renderer.render_to_string(&ledger).expect("renders"));
+        assert!(matches!(
+            renderer.render_fmt(&ledger, &mut Failing),
+            Err(BasicRendererError::Fmt(_))
+        ));
+    }
+
+    #[test]
+    fn postings_on_separate_lines() {
+        let rendered = round_trip(
+            &BasicRenderer::default(),
+            "2019-01-02 * \"Coffee\"\n\
+             \tAssets:Cash\t-3 USD\n\t\treceipt: \"r.pdf\"\n\tExpenses:Food\t3 USD\n",
+        );
+        assert_eq!(
+            rendered,
+            "2019-01-02 * \"Coffee\"\n\
+             \tAssets:Cash\t-3 USD\n\
+             \t\treceipt: \"r.pdf\"\n\
+             \tExpenses:Food\t3 USD\n"
+        );
+    }
+
+    #[test]
+    fn meta_comment_keys() {
+        let renderer = BasicRenderer {
+            meta_comment_keys: HashSet::from(["import-id".to_string()]),
+            ..BasicRenderer::default()
+        };
+        assert_eq!(
+            render(
+                &renderer,
+                "2019-01-02 * \"Coffee\"\n\
+                 \timport-id: \"bank-123\"\n\
+                 \treceipt: \"r.pdf\"\n\
+                 \tAssets:Cash\t-3 USD\n\
+                 \tExpenses:Food\n",
+            ),
+            "2019-01-02 * \"Coffee\"\n\
+             \treceipt: \"r.pdf\"\n\
+             \t; import-id: bank-123\n\
+             \tAssets:Cash\t-3 USD\n\
+             \tExpenses:Food\n"
+        );
+    }
+
+    #[test]
+    fn split_by_month() {
+        let ledger = parse(
+            "option \"title\" \"Test\"\n\
+             2019-01-02 * \"Coffee\"\n  Assets:Cash -3 USD\n  Expenses:Food\n\
+             2019-01-31 * \"Tea\"\n  Assets:Cash -2 USD\n  Expenses:Food\n\
+             2019-02-01 * \"Cake\"\n  Assets:Cash -4 USD\n  Expenses:Food\n",
+        );
+        let buffers = BasicRenderer::default()
+            .render_split_by_month(&ledger)
+            .expect("renders");
+        let mut keys: Vec<_> = buffers.keys().copied().collect();
+        keys.sort_unstable();
+        assert_eq!(keys, [(0, 0), (2019, 1), (2019, 2)]);
+        assert!(buffers[&(0, 0)].contains("option \"title\" \"Test\""));
+        assert!(buffers[&(2019, 1)].contains("\"Coffee\""));
+        assert!(buffers[&(2019, 1)].contains("\"Tea\""));
+        assert!(!buffers[&(2019, 1)].contains("\"Cake\""));
+        assert!(buffers[&(2019, 2)].contains("\"Cake\""));
+    }
+
+    #[test]
+    fn transaction_header_and_postings() {
+        assert_eq!(
+            round_trip(
+                &BasicRenderer::default(),
+                "2019-01-02 * \"Cafe\" \"Coffee\" #food ^receipt-1\n\
+                 \x20 Assets:Cash -3 USD\n\
+                 \x20 Expenses:Food 3 USD\n",
+            ),
+            "2019-01-02 * \"Cafe\" \"Coffee\" #food ^receipt-1\n\
+             \tAssets:Cash\t-3 USD\n\
+             \tExpenses:Food\t3 USD\n"
+        );
+    }
+
+    #[test]
+    fn cost_before_price() {
+        let rendered = round_trip(
+            &BasicRenderer::default(),
+            "2019-01-02 * \"Buy\"\n\
+             \tAssets:Broker\t10 AAPL {100 USD} @ 110 USD\n\
+             \tAssets:Cash\n",
+        );
+        assert!(rendered.contains("\n\tAssets:Broker\t10 AAPL {100 USD} @ 110 USD\n"));
+        match &parse(&rendered).directives[0] {
+            Directive::Transaction(transaction) => {
+                let posting = &transaction.postings[0];
+                let cost = posting.cost.as_ref().expect("cost");
+                assert_eq!(cost.number_per, Some(Decimal::from(100)));
+                assert_eq!(cost.currency, Some("USD"));
+                let price = posting.price.as_ref().expect("price");
+                assert_eq!(price.num, Some(Decimal::from(110)));
+                assert_eq!(price.currency, Some("USD"));
+            }
+            directive => panic!("expected a transaction, got {:?}", directive),
+        }
+    }
+
+    #[test]
+    fn trailing_balances() {
+        let renderer = BasicRenderer {
+            trailing_balances: true,
+            ..BasicRenderer::default()
+        };
+        let rendered = render(
+            &renderer,
+            "2019-01-31 * \"Deposit\"\n\tAssets:Bank\t100 USD\n\tAssets:Bank\t-100 USD\n",
+        );
+        assert_eq!(
+            rendered,
+            "2019-01-31 * \"Deposit\"\n\
+             \tAssets:Bank\t100 USD\n\
+             \tAssets:Bank\t-100 USD\n\
+             \n\
+             2019-02-01 balance Assets:Bank\t0 USD\n"
+        );
+        assert_eq!(parse(&rendered).directives.len(), 2);
+    }
+
+    #[test]
+    fn option_value_with_quote() {
+        let rendered = round_trip(
+            &BasicRenderer::default(),
+            "option \"title\" \"Thomas' \\\"home\\\" ledger\"\n",
+        );
+        assert_eq!(rendered, "option \"title\" \"Thomas' \\\"home\\\" ledger\"\n");
+        match &parse(&rendered).directives[0] {
+            Directive::Option(option) => {
+                assert_eq!(option.name, "title");
+                assert_eq!(option.val, "Thomas' \"home\" ledger");
+            }
+            directive => panic!("expected an option, got {:?}", directive),
+        }
+    }
+
+    #[test]
+    fn price_operators() {
+        let per_unit = "2019-01-02 * \"Buy\"\n\tAssets:Broker\t10 AAPL @ 110 USD\n\tAssets:Cash\n";
+        assert_eq!(round_trip(&BasicRenderer::default(), per_unit), per_unit);
+        let renderer = BasicRenderer {
+            total_prices: true,
+            ..BasicRenderer::default()
+        };
+        let total = "2019-01-02 * \"Buy\"\n\tAssets:Broker\t10 AAPL @@ 1100 USD\n\tAssets:Cash\n";
+        assert_eq!(round_trip(&renderer, total), total);
+    }
+
+    #[test]
+    fn indentation_styles() {
+        let source = "2019-01-02 * \"Coffee\"\n\
+                      \treceipt: \"r.pdf\"\n\
+                      \tAssets:Cash\t-3 USD\n\
+                      \t\tnote: \"tip\"\n\
+                      \tExpenses:Food\n";
+        for (indent, unit) in [
+            (Indent::Tab, "\t"),
+            (Indent::Spaces(2), "  "),
+            (Indent::Spaces(4), "    "),
+        ] {
+            let renderer = BasicRenderer {
+                indent,
+                ..BasicRenderer::default()
+            };
+            let expected = format!(
+                "2019-01-02 * \"Coffee\"\n\
+                 {0}receipt: \"r.pdf\"\n\
+                 {0}Assets:Cash\t-3 USD\n\
+                 {0}{0}note: \"tip\"\n\
+                 {0}Expenses:Food\n",
+                unit
+            );
+            assert_eq!(round_trip(&renderer, source), expected);
+        }
+    }
+
+    #[test]
+    fn aligned_currencies_with_tab_prefix() {
+        let renderer = BasicRenderer {
+            align_currency: Some(20),
+            ..BasicRenderer::default()
+        };
+        let rendered = round_trip(
+            &renderer,
+            "2019-01-02 * \"Shop\"\n\
+             \tAssets:Cash\t-1234.50 USD\n\
+             \tExpenses:Groceries:Vegetables\t4 USD\n\
+             \tExpenses:Food\t1230.50 USD\n",
+        );
+        let columns: Vec<_> = rendered
+            .lines()
+            .skip(1)
+            .map(|line| {
+                assert!(!line.trim_start().contains('\t'));
+                line.find("USD").expect("currency")
+            })
+            .collect();
+        assert_eq!(columns.len(), 3);
+        assert!(columns.iter().all(|&column| column == columns[0]));
+    }
+
+    #[test]
+    fn precision_rounds_half_away_from_zero() {
+        let renderer = BasicRenderer {
+            currency_precision: HashMap::from([("USD".to_string(), 2)]),
+            ..BasicRenderer::default()
+        };
+        let rendered = render(
+            &renderer,
+            "2019-01-02 * \"Rounding\"\n\
+             \tAssets:A\t1.005 USD\n\
+             \tAssets:B\t-1.005 USD\n\
+             \tAssets:C\t-0.001 USD\n\
+             \tAssets:D\t100 USD\n\
+             \tAssets:E\n",
+        );
+        assert!(rendered.contains("\tAssets:A\t1.01 USD\n"));
+        assert!(rendered.contains("\tAssets:B\t-1.01 USD\n"));
+        assert!(rendered.contains("\tAssets:C\t0.00 USD\n"));
+        assert!(rendered.contains("\tAssets:D\t100.00 USD\n"));
+    }
+
+    #[test]
+    fn meta_values_are_quoted_by_default() {
+        let source = "2019-01-02 open Assets:Cash\n\
+                      \tflag: \"TRUE\"\n\
+                      \tref: \"0042\"\n\
+                      \tstatus: \"PAID\"\n";
+        assert_eq!(round_trip(&BasicRenderer::default(), source), source);
+    }
+
+    #[test]
+    fn bare_and_quoted_meta_values() {
+        let renderer = BasicRenderer {
+            infer_value_types: true,
+            ..BasicRenderer::default()
+        };
+        let rendered = round_trip(
+            &renderer,
+            "2019-01-02 open Assets:Cash\n\
+             \ta: 1.2.3\n\
+             \tb: Income:\n\
+             \tc: 2019-13-45\n\
+             \td: \"text\"\n\
+             \te: 1,234.50\n\
+             \tf: 2019-01-31\n\
+             \tg: Income:Salary\n\
+             \th: 12,34\n\
+             \ti: Income:salary\n",
+        );
+        assert_eq!(
+            rendered,
+            "2019-01-02 open Assets:Cash\n\
+             \ta: \"1.2.3\"\n\
+             \tb: \"Income:\"\n\
+             \tc: \"2019-13-45\"\n\
+             \td: \"text\"\n\
+             \te: 1,234.50\n\
+             \tf: 2019-01-31\n\
+             \tg: Income:Salary\n\
+             \th: \"12,34\"\n\
+             \ti: \"Income:salary\"\n"
+        );
+    }
+
+    #[test]
+    fn document_tags_and_links() {
+        let source = "2019-01-02 document Assets:Cash \"receipt.pdf\" #cash ^count\n\
+                      \tsource: \"scan\"\n";
+        assert_eq!(round_trip(&BasicRenderer::default(), source), source);
+    }
+
+    #[test]
+    fn note_tags_and_links() {
+        let source = "2019-01-02 note Assets:Cash \"Counted\" #cash #audit ^count\n";
+        assert_eq!(round_trip(&BasicRenderer::default(), source), source);
+    }
+
+    #[test]
+    fn pushed_tags_are_rendered_on_transactions() {
+        let rendered = render(
+            &BasicRenderer::default(),
+            "pushtag #trip\n\
+             2019-01-02 * \"Coffee\"\n\
+             \tAssets:Cash\t-3 USD\n\
+             \tExpenses:Food\n\
+             poptag #trip\n\
+             2019-01-03 * \"Tea\"\n\
+             \tAssets:Cash\t-2 USD\n\
+             \tExpenses:Food\n",
+        );
+        assert!(rendered.contains("2019-01-02 * \"Coffee\" #trip\n"));
+        assert!(rendered.contains("2019-01-03 * \"Tea\"\n"));
+        assert!(!rendered.contains("pushtag") && !rendered.contains("poptag"));
+    }
+
+    #[test]
+    fn skip_unsupported_directives() {
+        let mut ledger = parse("2019-01-01 open Assets:Cash\n2019-01-02 close Assets:Cash\n");
+        ledger.directives.insert(1, Directive::Unsupported);
+        let mut renderer = BasicRenderer::default();
+        assert!(matches!(
+            renderer.render_to_string(&ledger),
+            Err(BasicRendererError::Unsupported { .. })
+        ));
+        renderer.skip_unsupported = true;
+        assert_eq!(
+            renderer.render_to_string(&ledger).expect("renders"),
+            "2019-01-01 open Assets:Cash\n\n2019-01-02 close Assets:Cash\n"
+        );
+    }
+
+    #[test]
+    fn group_digits_without_display_mode() {
+        let renderer = BasicRenderer {
+            group_digits: Some(','),
+            ..BasicRenderer::default()
+        };
+        let rendered = render(
+            &renderer,
+            "2019-01-02 * \"Grouping\"\n\
+             \tAssets:A\t1234567.89 USD\n\
+             \tAssets:B\t-1234567.89 USD\n\
+             \tAssets:C\t0.12345 USD\n\
+             \tAssets:D\t-123 USD\n\
+             \tAssets:E\n",
+        );
+        assert!(rendered.contains("\tAssets:A\t1,234,567.89 USD\n"));
+        assert!(rendered.contains("\tAssets:B\t-1,234,567.89 USD\n"));
+        assert!(rendered.contains("\tAssets:C\t0.12345 USD\n"));
+        assert!(rendered.contains("\tAssets:D\t-123 USD\n"));
+    }
+
+    #[test]
+    fn include_filename_with_space() {
+        let source = "include \"my accounts.beancount\"\n";
+        assert_eq!(round_trip(&BasicRenderer::default(), source), source);
+    }
+
+    #[test]
+    fn sort_by_date_is_stable() {
+        let renderer = BasicRenderer {
+            sort_by_date: true,
+            ..BasicRenderer::default()
+        };
+        let rendered = render(
+            &renderer,
+            "2019-01-03 open Assets:C\n\
+             2019-01-01 open Assets:B\n\
+             option \"title\" \"Test\"\n\
+             2019-01-01 open Assets:A\n\
+             plugin \"module\"\n\
+             2019-01-02 open Assets:D\n",
+        );
+        assert_eq!(
+            rendered,
+            "option \"title\" \"Test\"\n\n\
+             plugin \"module\"\n\n\
+             2019-01-01 open Assets:B\n\n\
+             2019-01-01 open Assets:A\n\n\
+             2019-01-02 open Assets:D\n\n\
+             2019-01-03 open Assets:C\n"
+        );
+    }
+
+    #[test]
+    fn posting_meta_is_indented_deeper() {
+        let source = "2019-01-02 * \"Coffee\"\n\
+                      \treceipt: \"r.pdf\"\n\
+                      \tAssets:Cash\t-3 USD\n\
+                      \t\tnote: \"tip\"\n\
+                      \tExpenses:Food\n";
+        let rendered = round_trip(&BasicRenderer::default(), source);
+        assert_eq!(rendered, source);
+        let ledger = parse(&rendered);
+        match &ledger.directives[0] {
+            Directive::Transaction(transaction) => {
+                assert_eq!(transaction.meta.get("receipt"), Some(&"r.pdf"));
+                assert!(!transaction.meta.contains_key("note"));
+                assert_eq!(transaction.postings[0].meta.get("note"), Some(&"tip"));
+            }
+            _ => panic!("expected a transaction"),
+        }
+    }
+
+    #[test]
+    fn line_endings() {
+        let source = "2019-01-01 open Assets:Cash\n\
+                      \tnote: \"cash\"\n\
+                      2019-01-02 * \"Coffee\"\n\
+                      \tAssets:Cash\t-3 USD\n\
+                      \tExpenses:Food\n";
+        let lf = "2019-01-01 open Assets:Cash\n\
+                  \tnote: \"cash\"\n\
+                  \n\
+                  2019-01-02 * \"Coffee\"\n\
+                  \tAssets:Cash\t-3 USD\n\
+                  \tExpenses:Food\n";
+        assert_eq!(render(&BasicRenderer::default(), source).as_bytes(), lf.as_bytes());
+        let renderer = BasicRenderer {
+            line_ending: LineEnding::Crlf,
+            ..BasicRenderer::default()
+        };
+        assert_eq!(
+            render(&renderer, source).as_bytes(),
+            lf.replace('\n', "\r\n").as_bytes()
+        );
+    }
+
+    #[test]
+    fn balance_tolerance_round_trip() {
+        let renderer = BasicRenderer::default();
+        let with = round_trip(&renderer, "2019-01-01 balance Assets:Cash 100.00 ~ 0.01 USD\n");
+        assert_eq!(with, "2019-01-01 balance Assets:Cash\t100.00 ~ 0.01 USD\n");
+        match &parse(&with).directives[0] {
+            Directive::Balance(balance) => {
+                assert_eq!(balance.tolerance, Some(Decimal::new(1, 2)));
+            }
+            _ => panic!("expected a balance"),
+        }
+        let without = round_trip(&renderer, "2019-01-01 balance Assets:Cash 100.00 USD\n");
+        assert!(!without.contains('~'));
+    }
+
+    #[test]
+    fn txn_flag_style() {
+        let source = "2019-01-02 * \"Coffee\"\n\
+                      \tAssets:Cash\t-3 USD\n\
+                      \tExpenses:Food\n\
+                      2019-01-03 ! \"Tea\"\n\
+                      \tAssets:Cash\t-2 USD\n\
+                      \tExpenses:Food\n";
+        let symbol = round_trip(&BasicRenderer::default(), source);
+        assert!(symbol.contains("2019-01-02 * \"Coffee\"\n"));
+        assert!(symbol.contains("2019-01-03 ! \"Tea\"\n"));
+        let renderer = BasicRenderer {
+            flag_style: FlagStyle::Txn,
+            ..BasicRenderer::default()
+        };
+        let txn = round_trip(&renderer, source);
+        assert!(txn.contains("2019-01-02 txn \"Coffee\"\n"));
+        assert!(txn.contains("2019-01-03 ! \"Tea\"\n"));
+    }
+
+    #[test]
+    fn open_without_currencies() {
+        let source = "2019-01-01 open Assets:Cash\n";
+        let rendered = round_trip(&BasicRenderer::default(), source);
+        assert_eq!(rendered, source);
+        assert!(rendered.lines().all(|line| !line.ends_with(' ')));
+        assert_eq!(
+            round_trip(&BasicRenderer::default(), "2019-01-01 open Assets:Cash \"fifo\"\n"),
+            "2019-01-01 open Assets:Cash \"FIFO\"\n"
+        );
+    }
+
+    #[test]
+    fn decimal_point_alignment() {
+        let renderer = BasicRenderer {
+            align_currency: Some(10),
+            alignment: Alignment::DecimalPoint,
+            ..BasicRenderer::default()
+        };
+        let rendered = round_trip(
+            &renderer,
+            "2019-01-02 * \"Shop\"\n\
+             \tAssets:Cash\t-1234.5 USD\n\
+             \tExpenses:Groceries\t1.125 USD\n\
+             \tExpenses:Food\t1233.375 USD\n",
+        );
+        let postings: Vec<_> = rendered.lines().skip(1).collect();
+        assert_eq!(postings.len(), 3);
+        let points: Vec<_> = postings.iter().map(|line| line.find('.')).collect();
+        let currencies: Vec<_> = postings.iter().map(|line| line.find("USD")).collect();
+        assert!(points.iter().all(|&point| point.is_some() && point == points[0]));
+        assert!(currencies.iter().all(|&currency| currency == currencies[0]));
+    }
+
+    #[test]
+    fn leading_comment_above_transaction() {
+        let comments = HashMap::from([("Coffee".to_string(), "Morning coffee".to_string())]);
+        let renderer = BasicRenderer {
+            leading_comments: Some(CommentHook::new(move |directive| match directive {
+                Directive::Transaction(transaction) => {
+                    comments.get(transaction.narration).cloned().into_iter().collect()
+                }
+                _ => Vec::new(),
+            })),
+            ..BasicRenderer::default()
+        };
+        let renderer = renderer.clone();
+        assert_eq!(
+            render(
+                &renderer,
+                "2019-01-01 open Assets:Cash\n\
+                 2019-01-02 * \"Coffee\"\n\
+                 \tAssets:Cash\t-3 USD\n\
+                 \tExpenses:Food\n",
+            ),
+            "2019-01-01 open Assets:Cash\n\
+             \n\
+             ; Morning coffee\n\
+             2019-01-02 * \"Coffee\"\n\
+             \tAssets:Cash\t-3 USD\n\
+             \tExpenses:Food\n"
+        );
+        assert!(format!("{:?}", renderer).contains("CommentHook(..)"));
+        let hook = renderer.leading_comments.expect("hook");
+        assert_eq!(hook.clone(), hook);
+        assert_ne!(CommentHook::new(|_| Vec::new()), hook);
+    }
+
+    #[test]
+    fn separator_policies() {
+        let source = "2019-01-01 open Assets:Cash\n\
+                      2019-01-01 open Expenses:Food\n\
+                      2019-01-02 close Expenses:Food\n";
+        let rendered = |separator| {
+            let renderer = BasicRenderer {
+                separator,
+                ..BasicRenderer::default()
+            };
+            render(&renderer, source)
+        };
+        assert_eq!(
+            rendered(Separator::Single),
+            "2019-01-01 open Assets:Cash\n\n\
+             2019-01-01 open Expenses:Food\n\n\
+             2019-01-02 close Expenses:Food\n"
+        );
+        assert_eq!(
+            rendered(Separator::None),
+            "2019-01-01 open Assets:Cash\n\
+             2019-01-01 open Expenses:Food\n\
+             2019-01-02 close Expenses:Food\n"
+        );
+        assert_eq!(
+            rendered(Separator::GroupByDate),
+            "2019-01-01 open Assets:Cash\n\
+             2019-01-01 open Expenses:Food\n\n\
+             2019-01-02 close Expenses:Food\n"
+        );
+    }
+
+    #[test]
+    fn split_and_sorted_output_is_separated() {
+        let ledger = parse(
+            "2019-01-01 open Expenses:Food\n\
+             2019-01-01 open Assets:Cash\n\
+             2019-01-02 close Expenses:Food\n",
+        );
+        let renderer = BasicRenderer::default();
+        let roots = renderer.render_split_by_root(&ledger).expect("renders");
+        assert_eq!(
+            roots[&AccountType::Expenses],
+            "2019-01-01 open Expenses:Food\n\n2019-01-02 close Expenses:Food\n"
+        );
+        let months = renderer.render_split_by_month(&ledger).expect("renders");
+        assert!(!months[&(2019, 1)].ends_with("\n\n"));
+        let mut sorted = Vec::new();
+        renderer.render_sorted_accounts(&ledger, &mut sorted).expect("renders");
+        assert_eq!(
+            String::from_utf8(sorted).expect("utf-8"),
+            "2019-01-01 open Assets:Cash\n\n\
+             2019-01-01 open Expenses:Food\n\n\
+             2019-01-02 close Expenses:Food\n"
+        );
+    }
+
+    #[test]
+    fn custom_string_and_number_args() {
+        let source = "2019-01-01 custom \"budget\" \"USD\" \"2019-01-01\" \"monthly food\"\n";
+        assert_eq!(round_trip(&BasicRenderer::default(), source), source);
+        let renderer = BasicRenderer {
+            infer_value_types: true,
+            ..BasicRenderer::default()
+        };
+        let source = "2019-01-01 custom \"budget\" \"monthly food\" 100.00 TRUE\n";
+        let rendered = round_trip(&renderer, source);
+        assert_eq!(rendered, source);
+        match &parse(&rendered).directives[0] {
+            Directive::Custom(custom) => {
+                assert_eq!(custom.args, vec!["monthly food", "100.00", "TRUE"]);
+            }
+            _ => panic!("expected a custom directive"),
+        }
+    }
+
+    #[test]
+    fn numeric_option_is_quoted_once() {
+        let source = "option \"inferred_tolerance_multiplier\" \"1.1\"\n";
+        let rendered = round_trip(&BasicRenderer::default(), source);
+        assert_eq!(rendered, source);
+        assert!(!rendered.contains("\"\"") && !rendered.contains("\\\""));
+    }
+
+    #[test]
+    fn cost_number_forms() {
+        let renderer = BasicRenderer::default();
+        for cost in ["{10 USD}", "{{20 USD}}", "{10 # 20 USD}", "{10 USD, 2019-01-01}"] {
+            let source = format!(
+                "2019-01-02 * \"Buy\"\n\
+                 \tAssets:Stock\t2 HOOL {}\n\
+                 \tAssets:Cash\n",
+                cost
+            );
+            assert_eq!(round_trip(&renderer, &source), source);
+        }
+        let ledger = parse("2019-01-02 * \"Buy\"\n\tAssets:Stock\t2 HOOL {10 # 20 USD}\n");
+        match &ledger.directives[0] {
+            Directive::Transaction(transaction) => {
+                let cost = transaction.postings[0].cost.as_ref().expect("cost");
+                assert_eq!(cost.number_per, Some(Decimal::new(10, 0)));
+                assert_eq!(cost.number_total, Some(Decimal::new(20, 0)));
+            }
+            _ => panic!("expected a transaction"),
+        }
+    }
+
+    #[test]
+    fn empty_and_label_only_costs() {
+        let renderer = BasicRenderer::default();
+        for cost in ["{}", "{\"lot-id\"}"] {
+            let source = format!(
+                "2019-01-02 * \"Sell\"\n\
+                 \tAssets:Stock\t-2 HOOL {}\n\
+                 \tAssets:Cash\n",
+                cost
+            );
+            assert_eq!(round_trip(&renderer, &source), source);
+        }
+    }
+
+    #[test]
+    fn merge_cost_marker() {
+        let renderer = BasicRenderer::default();
+        for cost in ["{*}", "{*, 2019-01-01}"] {
+            let source = format!(
+                "2019-01-02 * \"Sell\"\n\
+                 \tAssets:Stock\t-2 HOOL {}\n\
+                 \tAssets:Cash\n",
+                cost
+            );
+            assert_eq!(round_trip(&renderer, &source), source);
+        }
+    }
+
+    #[test]
+    fn explicit_sign() {
+        for display_mode in [false, true] {
+            let renderer = BasicRenderer {
+                display_mode,
+                explicit_sign: true,
+                ..BasicRenderer::default()
+            };
+            let rendered = render(
+                &renderer,
+                "2019-01-02 * \"Signs\"\n\
+                 \tAssets:A\t3 USD\n\
+                 \tAssets:B\t-3 USD\n\
+                 \tAssets:C\t0 USD\n",
+            );
+            assert!(rendered.contains("\tAssets:A\t+3 USD\n"));
+            assert!(rendered.contains("\tAssets:B\t-3 USD\n"));
+            assert!(rendered.contains("\tAssets:C\t0 USD\n"));
+        }
+    }
+
+    #[test]
+    fn flags_re_parse() {
+        let renderer = BasicRenderer::default();
+        for flag in ["*", "!", "#", "%", "&", "?", "P", "'A", "'Z"] {
+            let source = format!(
+                "2019-01-02 {} \"Coffee\"\n\
+                 \t{} Assets:Cash\t-3 USD\n\
+                 \tExpenses:Food\n",
+                flag, flag
+            );
+            assert_eq!(round_trip(&renderer, &source), source);
+        }
+        let strict = BasicRenderer {
+            strict: true,
+            ..BasicRenderer::default()
+        };
+        let ledger = parse("2019-01-02 * \"Coffee\"\n\t* Assets:Cash\t-3 USD\n");
+        for flag in ["AB", "x"] {
+            let mut transaction = match &ledger.directives[0] {
+                Directive::Transaction(transaction) => transaction.clone(),
+                _ => panic!("expected a transaction"),
+            };
+            transaction.postings[0].flag = Some(Flag::Other(flag.to_string()));
+            assert!(matches!(
+                strict.render_to_string(&transaction),
+                Err(BasicRendererError::InvalidPostingFlag(invalid)) if invalid == flag
+            ));
+            transaction.flag = Flag::Other(flag.to_string());
+            assert!(matches!(
+                strict.render_to_string(&transaction),
+                Err(BasicRendererError::InvalidTransactionFlag(invalid)) if invalid == flag
+            ));
+        }
+    }
+
+    #[test]
+    fn pad_target_and_source() {
+        let source = "2019-01-01 pad Assets:Cash Equity:Opening-Balances\n";
+        let rendered = round_trip(&BasicRenderer::default(), source);
+        assert_eq!(rendered, source);
+        match &parse(&rendered).directives[0] {
+            Directive::Pad(pad) => {
+                assert_eq!(pad.pad_to_account.ty, AccountType::Assets);
+                assert_eq!(pad.pad_from_account.ty, AccountType::Equity);
+            }
+            _ => panic!("expected a pad"),
+        }
+    }
+
+    #[test]
+    fn render_each_matches_ledger() {
+        let ledger = parse(
+            "2019-01-01 open Assets:Cash\n\
+             2019-01-02 * \"Coffee\"\n\
+             \tAssets:Cash\t-3 USD\n\
+             \tExpenses:Food\n\
+             2019-01-03 close Assets:Cash\n",
+        );
+        let renderer = BasicRenderer::default();
+        let directives: Vec<Directive<'_>> = ledger.directives.clone();
+        let mut streamed = Vec::new();
+        renderer.render_each(&directives, &mut streamed).expect("renders");
+        assert_eq!(
+            String::from_utf8(streamed).expect("utf-8"),
+            renderer.render_to_string(&ledger).expect("renders")
+        );
+    }
+
+    #[test]
+    fn append_summary_leaves_out_skipped_directives() {
+        let mut ledger = parse("2019-01-01 open Assets:Cash\n2019-01-02 close Assets:Cash\n");
+        ledger.directives.insert(1, Directive::Unsupported);
+        let renderer = BasicRenderer {
+            skip_unsupported: true,
+            append_summary: true,
+            ..BasicRenderer::default()
+        };
+        let rendered = renderer.render_to_string(&ledger).expect("renders");
+        assert_eq!(rendered.lines().last(), Some("; 2 directives, 0 transactions"));
+    }
+
+    #[test]
+    fn render_each_applies_per_directive_options() {
+        let source = "2019-01-02 open Assets:Cash\n\
+                      2019-01-01 close Assets:Cash\n\
+                      2019-01-03 balance Assets:Cash 0 USD\n";
+        let ledger = parse(source);
+        let renderer = BasicRenderer {
+            warn_date_order: true,
+            debug_indices: true,
+            ..BasicRenderer::default()
+        };
+        let mut streamed = Vec::new();
+        renderer
+            .render_each(&ledger.directives, &mut streamed)
+            .expect("renders");
+        let streamed = String::from_utf8(streamed).expect("utf-8");
+        assert_eq!(streamed, renderer.render_to_string(&ledger).expect("renders"));
+        assert!(streamed.contains("; WARNING: date out of order\n"));
+    }
+
+    #[test]
+    fn unsupported_error_names_index() {
+        let mut ledger = parse("2019-01-01 open Assets:Cash\n2019-01-02 close Assets:Cash\n");
+        ledger.directives.insert(1, Directive::Unsupported);
+        let renderer = BasicRenderer::default();
+        let error = renderer.render_to_string(&ledger).expect_err("unsupported");
+        assert!(matches!(error, BasicRendererError::Unsupported { index: Some(1) }));
+        assert_eq!(error.to_string(), "could not render unsupported directive at index 1");
+        let mut streamed = Vec::new();
+        let error = renderer
+            .render_each(&ledger.directives, &mut streamed)
+            .expect_err("unsupported");
+        assert!(error.to_string().contains("at index 1"));
+        let error = renderer
+            .render_to_string(&Directive::Unsupported)
+            .expect_err("unsupported");
+        assert_eq!(error.to_string(), "could not render unsupported directive");
+    }
+
+    #[test]
+    fn rendered_display_matches_render_to_string() {
+        let ledger = parse("2019-01-02 document Assets:Cash \"receipt.pdf\" #cash\n");
+        let document = match &ledger.directives[0] {
+            Directive::Document(document) => document,
+            _ => panic!("expected a document"),
+        };
+        assert_eq!(
+            Rendered(document).to_string(),
+            BasicRenderer::default().render_to_string(document).expect("renders")
+        );
+    }
+
+    #[test]
+    fn balance_aligns_with_postings() {
+        let renderer = BasicRenderer {
+            indent: Indent::Spaces(2),
+            align_currency: Some(40),
+            ..BasicRenderer::default()
+        };
+        let rendered = round_trip(
+            &renderer,
+            "2019-01-02 * \"Coffee\"\n\
+             \tAssets:Cash\t-3.50 USD\n\
+             \tExpenses:Food\t3.50 USD\n\
+             2019-01-03 balance Assets:Cash 96.50 USD\n",
+        );
+        let columns: Vec<_> = rendered
+            .lines()
+            .filter_map(|line| line.find("USD"))
+            .collect();
+        assert_eq!(columns.len(), 3);
+        assert!(columns.iter().all(|&column| column == columns[0]));
+    }
+
+    #[test]
+    fn aligned_balance_in_display_mode() {
+        let renderer = BasicRenderer {
+            align_currency: Some(40),
+            display_mode: true,
+            currency_symbols: HashMap::from([("EUR".to_string(), "€".to_string())]),
+            ..BasicRenderer::default()
+        };
+        let rendered = render(
+            &renderer,
+            "2019-01-02 * \"Coffee\"\n\
+             \tAssets:Cash\t-3 EUR\n\
+             \tExpenses:Food\t3 EUR\n\
+             2019-01-03 balance Assets:Cash 10 EUR\n",
+        );
+        let balance = rendered.lines().last().expect("balance line");
+        assert!(balance.ends_with(" 10€"));
+        assert!(!balance.contains("EUR"));
+    }
+
+    #[test]
+    fn whole_file_alignment() {
+        let renderer = BasicRenderer {
+            indent: Indent::Spaces(2),
+            align_currency: Some(10),
+            alignment: Alignment::WholeFile,
+            ..BasicRenderer::default()
+        };
+        let rendered = round_trip(
+            &renderer,
+            "2019-01-02 * \"Coffee\"\n\
+             \tAssets:Cash\t-3 USD\n\
+             \tExpenses:Food\t3 USD\n\
+             2019-01-03 * \"Rent\"\n\
+             \tLiabilities:CreditCard:Bank-Of-Somewhere\t-1234567.89 USD\n\
+             \tExpenses:Housing:Rent\t1234567.89 USD\n\
+             2019-01-04 balance Assets:Checking:Main-Account-Of-The-Household 123456789.00 USD\n",
+        );
+        let columns: Vec<_> = rendered
+            .lines()
+            .filter_map(|line| line.find("USD"))
+            .collect();
+        assert_eq!(columns.len(), 5);
+        assert!(columns.iter().all(|&column| column == columns[0]));
+        let balance = rendered.lines().last().expect("balance");
+        assert!(balance.contains("Household 123456789.00 USD"));
+    }
+
+    #[test]
+    fn trailing_balances_aligned() {
+        let source = "2019-01-31 * \"Deposit\"\n\
+                      \tAssets:Checking:Main-Account-Of-The-Household\t100 USD\n\
+                      \tIncome:Salary\t-100 USD\n";
+        for alignment in [Alignment::Currency, Alignment::WholeFile] {
+            let renderer = BasicRenderer {
+                trailing_balances: true,
+                align_currency: Some(10),
+                alignment,
+                ..BasicRenderer::default()
+            };
+            let rendered = render(&renderer, source);
+            let columns: Vec<_> = rendered
+                .lines()
+                .filter_map(|line| line.find("USD"))
+                .collect();
+            assert_eq!(columns.len(), 4);
+            let balance = rendered.lines().last().expect("balance");
+            assert!(balance.starts_with("2019-02-01 balance Income:Salary "));
+            assert!(!balance.contains('\t'));
+            if let Alignment::WholeFile = alignment {
+                assert!(columns.iter().all(|&column| column == columns[0]));
+            }
+        }
+    }
+}
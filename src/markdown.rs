@@ -0,0 +1,110 @@
+use crate::{BasicRenderer, BasicRendererError, Renderer};
+use beancount::core::*;
+use std::io::prelude::*;
+
+/// Renders transactions as Markdown tables for reports. Unlike `BasicRenderer`, the output is not
+/// beancount and cannot be parsed back.
+#[derive(Clone, Default, Debug)]
+pub struct MarkdownRenderer {
+    /// Formats the dates, accounts and amounts that end up in the tables.
+    pub basic: BasicRenderer,
+}
+
+impl MarkdownRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Escape the characters that would break out of a table cell.
+fn cell(s: &str) -> String {
+    s.replace('|', "\\|").replace('\n', " ")
+}
+
+impl<'a, W: Write> Renderer<&'a Ledger<'_>, W> for MarkdownRenderer {
+    type Error = BasicRendererError;
+    fn render(&self, ledger: &'a Ledger<'_>, w: &mut W) -> Result<(), Self::Error> {
+        for directive in &ledger.directives {
+            if let Directive::Transaction(transaction) = directive {
+                self.render(transaction, w)?;
+                self.basic.newline(w)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> Renderer<&'a Transaction<'_>, W> for MarkdownRenderer {
+    type Error = BasicRendererError;
+    fn render(&self, transaction: &'a Transaction<'_>, w: &mut W) -> Result<(), Self::Error> {
+        write!(w, "**{}**", self.basic.format_date(&transaction.date))?;
+        if let Some(payee) = &transaction.payee {
+            write!(w, " {} —", payee)?;
+        }
+        write!(w, " {}", transaction.narration)?;
+        self.basic.newline(w)?;
+        self.basic.newline(w)?;
+        write!(w, "| Account | Amount |")?;
+        self.basic.newline(w)?;
+        write!(w, "| --- | --- |")?;
+        self.basic.newline(w)?;
+        for posting in &transaction.postings {
+            let account = self.basic.render_to_string(&posting.account)?;
+            let units = self.basic.render_to_string(&posting.units)?;
+            write!(w, "| {} | {} |", cell(&account), cell(&units))?;
+            self.basic.newline(w)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LineEnding;
+
+    #[test]
+    fn transaction_table() {
+        let ledger = beancount::parse(
+            "2019-01-02 * \"Cafe\" \"Coffee\"\n  Assets:Cash -3.50 USD\n  Expenses:Food\n",
+        )
+        .expect("valid ledger");
+        let mut rendered = Vec::new();
+        MarkdownRenderer::new()
+            .render(&ledger, &mut rendered)
+            .expect("renders");
+        assert_eq!(
+            String::from_utf8(rendered).expect("utf-8"),
+            "**2019-01-02** Cafe — Coffee\n\
+             \n\
+             | Account | Amount |\n\
+             | --- | --- |\n\
+             | Assets:Cash | -3.50 USD |\n\
+             | Expenses:Food |  |\n\
+             \n"
+        );
+    }
+
+    #[test]
+    fn crlf_line_endings() {
+        let ledger = beancount::parse("2019-01-02 * \"Coffee\"\n  Assets:Cash -3 USD\n")
+            .expect("valid ledger");
+        let renderer = MarkdownRenderer {
+            basic: BasicRenderer {
+                line_ending: LineEnding::Crlf,
+                ..BasicRenderer::default()
+            },
+        };
+        let mut rendered = Vec::new();
+        renderer.render(&ledger, &mut rendered).expect("renders");
+        assert_eq!(
+            String::from_utf8(rendered).expect("utf-8"),
+            "**2019-01-02** Coffee\r\n\
+             \r\n\
+             | Account | Amount |\r\n\
+             | --- | --- |\r\n\
+             | Assets:Cash | -3 USD |\r\n\
+             \r\n"
+        );
+    }
+}